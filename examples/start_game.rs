@@ -27,7 +27,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
   info!("Queuing library & version downloads");
   let manifest = version_manager.resolve_local_version(&version_id, true, true).await?;
-  if !manifest.applies_to_current_environment(&env_features) {
+  if !manifest.applies_to_environment(&env_features, &version_manager.platform) {
     return Err(format!("Version {} is is incompatible with the current environment", version_id).into());
   }
 
@@ -0,0 +1,275 @@
+use std::{ collections::HashMap, fs::{ self, File }, path::Path };
+
+use serde::{ Deserialize, Serialize };
+use serde_json::Value;
+use thiserror::Error;
+use uuid::Uuid;
+
+use super::auth::UserAuthentication;
+
+/// The kind of account, serialized as the `type` field of `launcher_accounts.json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AccountType {
+  /// Microsoft account (named `Xbox` by the vanilla launcher)
+  #[serde(rename = "Xbox")]
+  Microsoft,
+  Offline,
+  /// Account authenticated against a third-party (Yggdrasil compatible) server
+  ThirdParty,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MinecraftProfile {
+  pub id: Uuid,
+  pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Account {
+  /// Stable identifier of the account inside the `AccountManager`
+  pub local_id: String,
+  #[serde(rename = "type")]
+  pub account_type: AccountType,
+  /// Display name of the account (the login name for online accounts)
+  pub username: String,
+  pub minecraft_profile: MinecraftProfile,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub access_token: Option<String>,
+  /// Authentication server URL, only used by third-party accounts
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub auth_server: Option<String>,
+  #[serde(default = "default_persistent")]
+  pub persistent: bool,
+
+  /// Fields written by other launchers, preserved when saving
+  #[serde(flatten)]
+  pub extra: HashMap<String, Value>,
+}
+
+fn default_persistent() -> bool {
+  true
+}
+
+impl Account {
+  fn new(account_type: AccountType, auth_server: Option<&str>, authentication: &UserAuthentication) -> Self {
+    let local_id = Self::create_local_id(&account_type, auth_server, &authentication.uuid);
+    Self {
+      local_id,
+      account_type,
+      username: authentication.username.clone(),
+      minecraft_profile: MinecraftProfile { id: authentication.uuid, name: authentication.username.clone() },
+      access_token: authentication.access_token.clone(),
+      auth_server: auth_server.map(str::to_string),
+      persistent: true,
+      extra: HashMap::new(),
+    }
+  }
+
+  pub fn offline(username: &str) -> Self {
+    Self::new(AccountType::Offline, None, &UserAuthentication::offline(username))
+  }
+
+  pub fn microsoft(authentication: &UserAuthentication) -> Self {
+    Self::new(AccountType::Microsoft, None, authentication)
+  }
+
+  pub fn third_party(auth_server: &str, authentication: &UserAuthentication) -> Self {
+    Self::new(AccountType::ThirdParty, Some(auth_server), authentication)
+  }
+
+  /// Creates an id that stays the same for a given profile, so adding an account twice replaces it
+  fn create_local_id(account_type: &AccountType, auth_server: Option<&str>, uuid: &Uuid) -> String {
+    let name = format!("{:?}:{}:{}", account_type, auth_server.unwrap_or_default(), uuid);
+    Uuid::new_v3(&Uuid::NAMESPACE_OID, name.as_bytes()).simple().to_string()
+  }
+
+  pub fn is_offline(&self) -> bool {
+    self.account_type == AccountType::Offline
+  }
+
+  pub fn authentication(&self) -> UserAuthentication {
+    UserAuthentication {
+      username: self.minecraft_profile.name.clone(),
+      uuid: self.minecraft_profile.id,
+      access_token: self.access_token.clone(),
+    }
+  }
+}
+
+impl From<&Account> for UserAuthentication {
+  fn from(account: &Account) -> Self {
+    account.authentication()
+  }
+}
+
+/// Holds every account known to the launcher and the one currently selected.
+///
+/// The file layout follows the vanilla launcher's `launcher_accounts.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountManager {
+  #[serde(default)]
+  accounts: HashMap<String, Account>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  active_account_local_id: Option<String>,
+
+  #[serde(flatten)]
+  extra: HashMap<String, Value>,
+}
+
+impl AccountManager {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Loads the accounts file, returning an empty manager if it doesn't exist yet
+  pub fn load(path: &Path) -> Result<Self, AccountError> {
+    if !path.is_file() {
+      return Ok(Self::new());
+    }
+    let file = File::open(path)?;
+    let mut manager: Self = serde_json::from_reader(file)?;
+    for (local_id, account) in &mut manager.accounts {
+      account.local_id.clone_from(local_id);
+    }
+    Ok(manager)
+  }
+
+  pub fn save(&self, path: &Path) -> Result<(), AccountError> {
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent)?;
+    }
+    let persistent = Self {
+      accounts: self.accounts
+        .iter()
+        .filter(|(_, account)| account.persistent)
+        .map(|(id, account)| (id.clone(), account.clone()))
+        .collect(),
+      active_account_local_id: self.active_account_local_id.clone(),
+      extra: self.extra.clone(),
+    };
+    fs::write(path, serde_json::to_vec_pretty(&persistent)?)?;
+    Ok(())
+  }
+
+  pub fn accounts(&self) -> Vec<&Account> {
+    self.accounts.values().collect()
+  }
+
+  pub fn get(&self, local_id: &str) -> Option<&Account> {
+    self.accounts.get(local_id)
+  }
+
+  /// Adds an account (replacing an existing one with the same id) and returns its id.
+  /// The account is selected if no other account is.
+  pub fn add(&mut self, account: Account) -> String {
+    let local_id = account.local_id.clone();
+    self.accounts.insert(local_id.clone(), account);
+    if self.selected().is_none() {
+      self.active_account_local_id.replace(local_id.clone());
+    }
+    local_id
+  }
+
+  pub fn remove(&mut self, local_id: &str) -> Option<Account> {
+    let account = self.accounts.remove(local_id)?;
+    if self.active_account_local_id.as_deref() == Some(local_id) {
+      self.active_account_local_id.take();
+    }
+    Some(account)
+  }
+
+  /// Renames an account and returns its id. Offline accounts also get a new profile name and uuid,
+  /// so their id changes like if the account was added again under the new name.
+  ///
+  /// # Errors
+  /// Returns `AccountError::AlreadyExists` if an offline account is renamed to the name of another offline account.
+  pub fn rename(&mut self, local_id: &str, username: &str) -> Result<String, AccountError> {
+    let original = self.accounts.get(local_id).ok_or_else(|| AccountError::AccountNotFound(local_id.to_string()))?;
+    let mut account = original.clone();
+    account.username = username.to_string();
+    if account.is_offline() {
+      let authentication = UserAuthentication::offline(username);
+      account.minecraft_profile = MinecraftProfile { id: authentication.uuid, name: authentication.username };
+      account.local_id = Account::create_local_id(&account.account_type, account.auth_server.as_deref(), &account.minecraft_profile.id);
+    }
+
+    let new_id = account.local_id.clone();
+    if new_id != local_id && self.accounts.contains_key(&new_id) {
+      return Err(AccountError::AlreadyExists(new_id));
+    }
+    self.accounts.remove(local_id);
+    if self.active_account_local_id.as_deref() == Some(local_id) {
+      self.active_account_local_id.replace(new_id.clone());
+    }
+    self.accounts.insert(new_id.clone(), account);
+    Ok(new_id)
+  }
+
+  pub fn select(&mut self, local_id: &str) -> Result<(), AccountError> {
+    if !self.accounts.contains_key(local_id) {
+      return Err(AccountError::AccountNotFound(local_id.to_string()));
+    }
+    self.active_account_local_id.replace(local_id.to_string());
+    Ok(())
+  }
+
+  pub fn selected(&self) -> Option<&Account> {
+    self.active_account_local_id.as_ref().and_then(|id| self.accounts.get(id))
+  }
+
+  /// The authentication of the selected account, ready for `GameOptionsBuilder::authentication`
+  pub fn selected_authentication(&self) -> Option<UserAuthentication> {
+    self.selected().map(Account::authentication)
+  }
+}
+
+#[derive(Debug, Error)]
+pub enum AccountError {
+  #[error("Account not found: {0}")] AccountNotFound(String),
+  #[error("Account already exists: {0}")] AlreadyExists(String),
+  #[error(transparent)] IO(#[from] std::io::Error),
+  #[error(transparent)] Json(#[from] serde_json::Error),
+}
+
+#[cfg(test)]
+mod tests {
+  use std::env::temp_dir;
+
+  use super::*;
+
+  #[test]
+  fn test_accounts_roundtrip() {
+    let mut manager = AccountManager::new();
+    let first = manager.add(Account::offline("Player"));
+    let second = manager.add(Account::offline("Other"));
+    assert_eq!(manager.selected().map(|a| &a.local_id), Some(&first));
+    assert_eq!(manager.add(Account::offline("Player")), first);
+
+    manager.select(&second).unwrap();
+    let second = manager.rename(&second, "Renamed").unwrap();
+    assert_eq!(manager.selected().map(|a| &a.local_id), Some(&second));
+    let auth = manager.selected_authentication().unwrap();
+    assert_eq!(auth.username, "Renamed");
+    assert_eq!(auth.uuid, UserAuthentication::offline("Renamed").uuid);
+    assert_eq!(manager.add(Account::offline("Renamed")), second);
+    assert_eq!(manager.accounts().len(), 2);
+
+    // Renaming onto another offline account would replace it
+    assert!(matches!(manager.rename(&second, "Player"), Err(AccountError::AlreadyExists(id)) if id == first));
+    assert_eq!(manager.get(&second).unwrap().username, "Renamed");
+    assert_eq!(manager.get(&first).unwrap().username, "Player");
+    assert_eq!(manager.rename(&second, "Renamed").unwrap(), second);
+
+    let path = temp_dir().join(format!("mlc-accounts-{}.json", std::process::id()));
+    manager.save(&path).unwrap();
+    let mut loaded = AccountManager::load(&path).unwrap();
+    let _ = fs::remove_file(&path);
+    assert_eq!(loaded.accounts().len(), 2);
+    assert_eq!(loaded.selected().map(|a| &a.local_id), Some(&second));
+
+    loaded.remove(&second);
+    assert!(loaded.selected().is_none());
+  }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{ collections::HashMap, fmt::Display, mem::take, sync::Mutex };
 
 #[derive(Debug, Default)]
 pub struct ArgumentSubstitutorBuilder {
@@ -27,34 +27,151 @@ impl ArgumentSubstitutorBuilder {
   }
 }
 
+/// A piece of an argument template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplatePart<'a> {
+  Text(&'a str),
+  /// A `${name}` placeholder
+  Variable(&'a str),
+}
+
+/// An argument parsed into literal text and `${name}` placeholders
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArgumentTemplate<'a> {
+  pub parts: Vec<TemplatePart<'a>>,
+}
+
+impl<'a> ArgumentTemplate<'a> {
+  /// Parses `input` in a single pass. A `$` not followed by a closed `${...}` is kept as text.
+  pub fn parse(input: &'a str) -> Self {
+    let mut parts = vec![];
+    let mut rest = input;
+    while let Some(start) = rest.find("${") {
+      let Some(len) = rest[start + 2..].find('}') else {
+        break;
+      };
+      if start > 0 {
+        parts.push(TemplatePart::Text(&rest[..start]));
+      }
+      parts.push(TemplatePart::Variable(&rest[start + 2..start + 2 + len]));
+      rest = &rest[start + 2 + len + 1..];
+    }
+    if !rest.is_empty() {
+      parts.push(TemplatePart::Text(rest));
+    }
+    Self { parts }
+  }
+
+  pub fn variables(&self) -> Vec<&'a str> {
+    self.parts
+      .iter()
+      .filter_map(|part| if let TemplatePart::Variable(name) = part { Some(*name) } else { None })
+      .collect()
+  }
+}
+
+/// A placeholder that had no substitution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnresolvedVariable {
+  pub name: String,
+  /// The argument the placeholder was found in
+  pub argument: String,
+}
+
+impl Display for UnresolvedVariable {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "${{{}}} in {:?}", self.name, self.argument)
+  }
+}
+
 pub struct ArgumentSubstitutor {
   substitutions: HashMap<String, String>,
+  unresolved: Mutex<Vec<UnresolvedVariable>>,
 }
 
 impl ArgumentSubstitutor {
   pub fn new(substitutions: HashMap<String, String>) -> Self {
-    Self { substitutions }
+    Self { substitutions, unresolved: Mutex::default() }
   }
 
+  /// Replaces every `${name}` placeholder of `input`.
+  ///
+  /// Substituted values are never parsed again, and unknown placeholders are kept as-is
+  /// and recorded (see `ArgumentSubstitutor::take_unresolved`).
   pub fn substitute(&self, input: &str) -> String {
-    let mut output = input.to_string();
-    for (key, value) in &self.substitutions {
-      output = output.replace(&format!("${{{}}}", key), value);
+    let (output, unresolved) = self.substitute_template(input);
+    if !unresolved.is_empty() {
+      self.unresolved.lock().unwrap().extend(unresolved);
     }
     output
   }
 
-  pub fn substitute_all(&self, input: Vec<&str>) -> Vec<String> {
-    let mut output = input
-      .iter()
-      .map(|s| s.to_string())
-      .collect::<Vec<_>>();
-
-    for (key, value) in &self.substitutions {
-      for input in &mut output {
-        *input = input.replace(&format!("${{{}}}", key), value);
+  /// Like `ArgumentSubstitutor::substitute`, but returns the unknown placeholders of `input`
+  /// instead of recording them in the substitutor
+  pub fn substitute_template(&self, input: &str) -> (String, Vec<UnresolvedVariable>) {
+    let mut output = String::with_capacity(input.len());
+    let mut unresolved = vec![];
+    for part in ArgumentTemplate::parse(input).parts {
+      match part {
+        TemplatePart::Text(text) => output.push_str(text),
+        TemplatePart::Variable(name) => {
+          if let Some(value) = self.substitutions.get(name) {
+            output.push_str(value);
+          } else {
+            output.push_str(&format!("${{{name}}}"));
+            unresolved.push(UnresolvedVariable { name: name.to_string(), argument: input.to_string() });
+          }
+        }
       }
     }
-    output
+    (output, unresolved)
+  }
+
+  pub fn substitute_all(&self, input: Vec<&str>) -> Vec<String> {
+    input
+      .into_iter()
+      .map(|s| self.substitute(s))
+      .collect()
+  }
+
+  /// Returns the placeholders that couldn't be substituted so far, and clears them
+  pub fn take_unresolved(&self) -> Vec<UnresolvedVariable> {
+    take(&mut self.unresolved.lock().unwrap())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_substitute() {
+    let mut builder = ArgumentSubstitutorBuilder::new();
+    builder.add("auth_player_name", "${version_name}").add("version_name", "1.21");
+    let substitutor = builder.build();
+
+    assert_eq!(substitutor.substitute("--username ${auth_player_name}"), "--username ${version_name}");
+    assert_eq!(substitutor.substitute("$5 ${version_name}${unknown} ${"), "$5 1.21${unknown} ${");
+    assert_eq!(substitutor.take_unresolved(), vec![UnresolvedVariable {
+      name: "unknown".to_string(),
+      argument: "$5 ${version_name}${unknown} ${".to_string(),
+    }]);
+    assert!(substitutor.take_unresolved().is_empty());
+
+    let (output, unresolved) = substitutor.substitute_template("${version_name}-${missing}");
+    assert_eq!(output, "1.21-${missing}");
+    assert_eq!(unresolved.len(), 1);
+    assert!(substitutor.take_unresolved().is_empty());
+  }
+
+  #[test]
+  fn test_parse_template() {
+    let template = ArgumentTemplate::parse("-Xdock:icon=${asset=icons/minecraft.icns}!");
+    assert_eq!(template.parts, vec![
+      TemplatePart::Text("-Xdock:icon="),
+      TemplatePart::Variable("asset=icons/minecraft.icns"),
+      TemplatePart::Text("!")
+    ]);
+    assert_eq!(template.variables(), vec!["asset=icons/minecraft.icns"]);
   }
 }
@@ -3,9 +3,9 @@ use base64::engine::general_purpose;
 use serde::Deserialize;
 use serde_json::Value;
 use thiserror::Error;
-use reqwest::Client;
 use uuid::Uuid;
 
+#[allow(dead_code)]
 const PROFILE_URL: &str = "https://sessionserver.mojang.com/session/minecraft/profile/";
 
 #[derive(Debug, Clone)]
@@ -90,6 +90,7 @@ pub enum UserAuthenticationError {
 }
 
 #[derive(Deserialize, Debug)]
+#[allow(dead_code)]
 struct ProfileResponse {
   name: String,
   id: String,
@@ -1,10 +1,12 @@
-use std::{ path::PathBuf, time::SystemTimeError };
+use std::{ path::PathBuf, time::{ Duration, SystemTimeError } };
 
 use thiserror::Error;
 use zip::result::ZipError;
 
 use crate::version_manager::error::{ LoadVersionError, ResolveManifestError };
 
+use super::argument_substitutor::UnresolvedVariable;
+
 #[derive(Debug, Error)]
 pub enum Error {
   #[error(transparent)] IO(#[from] std::io::Error),
@@ -19,6 +21,9 @@ pub enum Error {
   #[error(transparent)] Zip(#[from] zip::result::ZipError),
   #[error("Classpath file not found: {0}")] ClasspathFileNotFound(PathBuf),
   #[error("Invalid classpath path: {0}")] InvalidClasspathPath(PathBuf),
+  #[error("Invalid JVM options! {0}")] JvmOptions(#[from] JvmOptionsError),
+  #[error("Hook failed! {0}")] Hook(#[from] HookError),
+  #[error("Unresolved variables: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))] UnresolvedVariables(Vec<UnresolvedVariable>),
 }
 
 #[derive(Debug, Error)]
@@ -27,6 +32,7 @@ pub enum UnpackNativesError {
   #[error("Failed to read native: {0}")] ReadNative(std::io::Error),
   #[error("Failed to unzip native: {0}")] UnzipNative(#[from] ZipError),
   #[error("Failed to unpack native: {0}")] UnpackNative(std::io::Error),
+  #[error("Refusing to unpack native {0}, it escapes the natives folder")] UnsafePath(String),
 }
 
 #[derive(Debug, Error)]
@@ -37,3 +43,28 @@ pub enum UnpackAssetsError {
   #[error("Failed to calculate checksum for asset object: {0}")] ChecksumAssetObject(#[source] std::io::Error),
   #[error("Failed to unpack asset object: {0}")] UnpackAssetObject(std::io::Error),
 }
+
+#[derive(Debug, Error)]
+pub enum JvmOptionsError {
+  #[error("Minimum heap size ({min}M) is larger than the maximum heap size ({max}M)")] InvalidHeap {
+    min: u64,
+    max: u64,
+  },
+}
+
+#[derive(Debug, Error)]
+pub enum HookError {
+  #[error("Hook command is empty")] EmptyCommand,
+  #[error("Failed to run hook '{command}': {source}")] Spawn {
+    command: String,
+    source: std::io::Error,
+  },
+  #[error("Hook '{command}' timed out after {}s", timeout.as_secs())] Timeout {
+    command: String,
+    timeout: Duration,
+  },
+  #[error("Hook '{command}' exited with code {}", code.map(|code| code.to_string()).unwrap_or("?".to_string()))] Failed {
+    command: String,
+    code: Option<i32>,
+  },
+}
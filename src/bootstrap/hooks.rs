@@ -0,0 +1,164 @@
+use std::{ path::{ Path, PathBuf }, process::Command, thread, time::{ Duration, Instant } };
+
+use log::{ info, warn };
+
+use super::{ argument_substitutor::ArgumentSubstitutor, error::HookError, script::REDACTED };
+
+/// Time a hook may run before it gets killed
+pub const DEFAULT_HOOK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A command run before the game starts or after it exits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookCommand {
+  /// Program followed by its arguments. Arguments may contain `${...}` launch variables.
+  pub command: Vec<String>,
+  /// Working directory of the hook (the game directory once the launch is prepared)
+  pub directory: Option<PathBuf>,
+  /// Kills the hook if it runs longer, `None` waits forever
+  pub timeout: Option<Duration>,
+  /// Whether a failing hook aborts the launch
+  pub abort_on_failure: bool,
+  /// Values (e.g. the access token) replaced with `REDACTED` when the command is logged
+  pub redactions: Vec<String>,
+}
+
+impl HookCommand {
+  pub fn new(command: Vec<impl AsRef<str>>) -> Self {
+    Self {
+      command: command
+        .iter()
+        .map(|s| s.as_ref().to_string())
+        .collect(),
+      directory: None,
+      timeout: Some(DEFAULT_HOOK_TIMEOUT),
+      abort_on_failure: false,
+      redactions: vec![],
+    }
+  }
+
+  pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+    self.timeout = timeout;
+    self
+  }
+
+  pub fn abort_on_failure(mut self, abort_on_failure: bool) -> Self {
+    self.abort_on_failure = abort_on_failure;
+    self
+  }
+
+  /// Hides every occurrence of `secret` from the logs and errors of the hook
+  pub fn redact(mut self, secret: impl AsRef<str>) -> Self {
+    if !secret.as_ref().is_empty() {
+      self.redactions.push(secret.as_ref().to_string());
+    }
+    self
+  }
+
+  /// The command line as it is logged, with the redacted values hidden
+  pub fn display_command(&self) -> String {
+    self.redactions.iter().fold(self.command.join(" "), |command, secret| command.replace(secret, REDACTED))
+  }
+
+  pub fn in_directory(mut self, directory: &Path) -> Self {
+    self.directory.get_or_insert_with(|| directory.to_path_buf());
+    self
+  }
+
+  /// Replaces the launch variables (`${game_directory}`, `${version_name}`, ...) in the command.
+  ///
+  /// Unknown variables are kept and only logged, they don't count as unresolved launch arguments.
+  pub fn substitute(&self, substitutor: &ArgumentSubstitutor) -> Self {
+    let mut command = Vec::with_capacity(self.command.len());
+    for argument in &self.command {
+      let (argument, unresolved) = substitutor.substitute_template(argument);
+      for variable in unresolved {
+        warn!("Unresolved variable in hook - {variable}");
+      }
+      command.push(argument);
+    }
+    Self { command, ..self.clone() }
+  }
+
+  /// Runs the hook and waits for it to finish
+  ///
+  /// # Errors
+  /// Returns a `HookError` if the hook couldn't be started, timed out or exited with a non-zero code.
+  pub fn run(&self) -> Result<(), HookError> {
+    let (program, args) = self.command.split_first().ok_or(HookError::EmptyCommand)?;
+    let command_line = self.display_command();
+    info!("Running hook {command_line}");
+
+    let mut command = Command::new(program);
+    command.args(args);
+    if let Some(directory) = &self.directory {
+      command.current_dir(directory);
+    }
+    let mut child = command.spawn().map_err(|source| HookError::Spawn { command: command_line.clone(), source })?;
+
+    let start_time = Instant::now();
+    let status = loop {
+      if let Some(status) = child.try_wait().map_err(|source| HookError::Spawn { command: command_line.clone(), source })? {
+        break status;
+      }
+      if let Some(timeout) = self.timeout {
+        if start_time.elapsed() > timeout {
+          let _ = child.kill();
+          let _ = child.wait();
+          return Err(HookError::Timeout { command: command_line, timeout });
+        }
+      }
+      thread::sleep(Duration::from_millis(50));
+    };
+
+    if !status.success() {
+      return Err(HookError::Failed { command: command_line, code: status.code() });
+    }
+    Ok(())
+  }
+}
+
+/// Runs the hooks in order. Failures are logged, and only returned for hooks marked `abort_on_failure`.
+pub fn run_hooks(hooks: &[HookCommand]) -> Result<(), HookError> {
+  for hook in hooks {
+    if let Err(err) = hook.run() {
+      if hook.abort_on_failure {
+        return Err(err);
+      }
+      warn!("{err}");
+    }
+  }
+  Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_run_hooks() {
+    assert!(HookCommand::new(vec!["true"]).run().is_ok());
+    assert!(matches!(HookCommand::new(vec!["sh", "-c", "exit 3"]).run(), Err(HookError::Failed { code: Some(3), .. })));
+
+    let slow = HookCommand::new(vec!["sleep", "5"]).with_timeout(Some(Duration::from_millis(100)));
+    assert!(matches!(slow.run(), Err(HookError::Timeout { .. })));
+
+    let failing = HookCommand::new(vec!["false"]);
+    assert!(run_hooks(std::slice::from_ref(&failing)).is_ok());
+    assert!(run_hooks(&[failing.abort_on_failure(true)]).is_err());
+  }
+
+  #[test]
+  fn test_substitute_hook() {
+    let substitutor = ArgumentSubstitutor::new([("version_name".to_string(), "1.21".to_string())].into());
+    let hook = HookCommand::new(vec!["echo", "${version_name}", "${missing}"]).substitute(&substitutor);
+    assert_eq!(hook.command, vec!["echo", "1.21", "${missing}"]);
+    assert!(substitutor.take_unresolved().is_empty());
+
+    let substitutor = ArgumentSubstitutor::new([("auth_access_token".to_string(), "secret-token".to_string())].into());
+    let hook = HookCommand::new(vec!["sh", "-c", "exit 1", "${auth_access_token}"]).substitute(&substitutor).redact("secret-token").redact("");
+    assert_eq!(hook.command[3], "secret-token");
+    assert_eq!(hook.display_command(), "sh -c exit 1 ?????");
+    let err = hook.run().unwrap_err().to_string();
+    assert!(!err.contains("secret-token"), "{err}");
+  }
+}
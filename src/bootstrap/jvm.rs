@@ -0,0 +1,190 @@
+use derive_builder::Builder;
+use log::warn;
+
+use super::error::JvmOptionsError;
+
+/// Largest heap (in MiB) a 32-bit JVM can reliably reserve
+pub const MAX_32BIT_HEAP_MB: u64 = 1536;
+
+/// JVM flags that only exist on some Java versions: (flag prefix, first supported major, last supported major)
+const VERSIONED_FLAGS: &[(&str, i64, i64)] = &[
+  ("-XX:PermSize", 0, 7),
+  ("-XX:MaxPermSize", 0, 7),
+  ("-XX:+AggressiveOpts", 0, 11),
+  ("-XX:+UseConcMarkSweepGC", 0, 13),
+  ("-XX:+CMSIncrementalMode", 0, 8),
+  ("-XX:+CMS", 0, 13),
+  ("-XX:CMS", 0, 13),
+  ("-XX:+UseBiasedLocking", 0, 17),
+  ("-XX:-UseBiasedLocking", 0, 17),
+  ("-XX:+UseZGC", 11, i64::MAX),
+  ("-XX:+ZGenerational", 21, i64::MAX),
+  ("-XX:+UseShenandoahGC", 12, i64::MAX),
+  ("--add-opens", 9, i64::MAX),
+  ("--add-exports", 9, i64::MAX),
+  ("--enable-native-access", 17, i64::MAX),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GarbageCollector {
+  G1,
+  Z,
+  Shenandoah,
+  /// Concurrent Mark Sweep, removed in Java 14
+  ConcMarkSweep,
+}
+
+impl GarbageCollector {
+  /// Returns whether the collector can be used on the given Java major version
+  pub fn is_supported(&self, java_major: i64) -> bool {
+    match self {
+      GarbageCollector::G1 => true,
+      GarbageCollector::Z => java_major >= 11,
+      GarbageCollector::Shenandoah => java_major >= 12,
+      GarbageCollector::ConcMarkSweep => java_major < 14,
+    }
+  }
+
+  pub fn arguments(&self, java_major: i64) -> Vec<&'static str> {
+    match self {
+      GarbageCollector::G1 =>
+        vec![
+          "-XX:+UnlockExperimentalVMOptions",
+          "-XX:+UseG1GC",
+          "-XX:G1NewSizePercent=20",
+          "-XX:G1ReservePercent=20",
+          "-XX:MaxGCPauseMillis=50",
+          "-XX:G1HeapRegionSize=32M"
+        ],
+      // ZGC is experimental before Java 15
+      GarbageCollector::Z if java_major < 15 => vec!["-XX:+UnlockExperimentalVMOptions", "-XX:+UseZGC"],
+      GarbageCollector::Z => vec!["-XX:+UseZGC"],
+      GarbageCollector::Shenandoah => vec!["-XX:+UseShenandoahGC"],
+      GarbageCollector::ConcMarkSweep =>
+        vec!["-XX:+UseConcMarkSweepGC", "-XX:+CMSIncrementalMode", "-XX:-UseAdaptiveSizePolicy", "-Xmn128M"],
+    }
+  }
+}
+
+/// Typed replacement for raw JVM arguments: heap sizes, garbage collector and extra flags
+#[derive(Debug, Clone, Builder)]
+#[builder(pattern = "owned", setter(strip_option))]
+pub struct JvmOptions {
+  #[builder(default)]
+  /// Initial heap size in MiB (`-Xms`)
+  pub min_heap_mb: Option<u64>,
+  #[builder(default = "Some(2048)")]
+  /// Maximum heap size in MiB (`-Xmx`)
+  pub max_heap_mb: Option<u64>,
+  #[builder(default = "Some(GarbageCollector::G1)")]
+  pub garbage_collector: Option<GarbageCollector>,
+  #[builder(default)]
+  /// Additional flags appended after the generated ones
+  pub extra_args: Vec<String>,
+}
+
+impl Default for JvmOptions {
+  fn default() -> Self {
+    JvmOptionsBuilder::default().build().expect("every field has a default")
+  }
+}
+
+impl JvmOptions {
+  /// Builds the JVM arguments for the target Java version.
+  ///
+  /// Flags the target Java version doesn't support are dropped, an unsupported garbage collector
+  /// falls back to G1, and heap sizes are clamped to `MAX_32BIT_HEAP_MB` on 32-bit JVMs.
+  ///
+  /// # Errors
+  /// Returns `JvmOptionsError::InvalidHeap` if the minimum heap is larger than the maximum heap.
+  pub fn arguments(&self, java_major: i64, is_64bit: bool) -> Result<Vec<String>, JvmOptionsError> {
+    let clamp = |heap: u64| {
+      if !is_64bit && heap > MAX_32BIT_HEAP_MB {
+        warn!("Heap size of {heap}M is too large for a 32-bit JVM, using {MAX_32BIT_HEAP_MB}M");
+        MAX_32BIT_HEAP_MB
+      } else {
+        heap
+      }
+    };
+    let min_heap = self.min_heap_mb.map(clamp);
+    let max_heap = self.max_heap_mb.map(clamp);
+
+    if let (Some(min), Some(max)) = (min_heap, max_heap) {
+      if min > max {
+        return Err(JvmOptionsError::InvalidHeap { min, max });
+      }
+    }
+
+    let mut arguments = vec![];
+    if let Some(min) = min_heap {
+      arguments.push(format!("-Xms{min}M"));
+    }
+    if let Some(max) = max_heap {
+      arguments.push(format!("-Xmx{max}M"));
+    }
+
+    if let Some(gc) = self.garbage_collector {
+      let gc = if gc.is_supported(java_major) {
+        gc
+      } else {
+        warn!("{gc:?} garbage collector is not supported on Java {java_major}, using G1");
+        GarbageCollector::G1
+      };
+      arguments.extend(
+        gc.arguments(java_major)
+          .into_iter()
+          .filter(|arg| Self::is_supported_flag(arg, java_major))
+          .map(str::to_string)
+      );
+    }
+
+    for arg in &self.extra_args {
+      if Self::is_supported_flag(arg, java_major) {
+        arguments.push(arg.clone());
+      } else {
+        warn!("Dropping JVM argument {arg}, it is not supported on Java {java_major}");
+      }
+    }
+
+    Ok(arguments)
+  }
+
+  pub fn is_supported_flag(arg: &str, java_major: i64) -> bool {
+    VERSIONED_FLAGS.iter()
+      .filter(|(prefix, ..)| arg.starts_with(prefix))
+      .all(|(_, min, max)| (*min..=*max).contains(&java_major))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_jvm_arguments() {
+    let options = JvmOptionsBuilder::default()
+      .min_heap_mb(4096)
+      .max_heap_mb(4096)
+      .garbage_collector(GarbageCollector::Z)
+      .extra_args(vec!["-XX:MaxPermSize=256M".into(), "-Dfoo=bar".into()])
+      .build()
+      .unwrap();
+
+    let java_21 = options.arguments(21, true).unwrap();
+    assert_eq!(java_21, vec!["-Xms4096M", "-Xmx4096M", "-XX:+UseZGC", "-Dfoo=bar"]);
+
+    let java_8 = options.arguments(8, false).unwrap();
+    assert_eq!(&java_8[..2], &["-Xms1536M", "-Xmx1536M"]);
+    assert!(java_8.contains(&"-XX:+UseG1GC".to_string()));
+    assert!(!java_8.contains(&"-XX:MaxPermSize=256M".to_string()));
+
+    let cms = JvmOptionsBuilder::default().garbage_collector(GarbageCollector::ConcMarkSweep).build().unwrap();
+    assert!(cms.arguments(8, true).unwrap().contains(&"-XX:+CMSIncrementalMode".to_string()));
+    let java_11 = cms.arguments(11, true).unwrap();
+    assert!(java_11.contains(&"-XX:+UseConcMarkSweepGC".to_string()));
+    assert!(!java_11.contains(&"-XX:+CMSIncrementalMode".to_string()));
+
+    let invalid = JvmOptionsBuilder::default().min_heap_mb(4096).max_heap_mb(1024).build().unwrap();
+    assert!(invalid.arguments(17, true).is_err());
+  }
+}
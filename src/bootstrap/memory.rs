@@ -0,0 +1,174 @@
+use std::fmt::Display;
+
+use crate::json::manifest::VersionManifest;
+
+/// Smallest heap the automatic strategy picks, unless the user set a lower limit
+pub const MIN_AUTO_HEAP_MB: u64 = 1024;
+/// Extra heap given to the game for every installed mod
+pub const HEAP_PER_MOD_MB: u64 = 32;
+/// Memory always left to the operating system
+pub const SYSTEM_RESERVED_MB: u64 = 2048;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SystemMemory {
+  pub total_mb: u64,
+  pub available_mb: u64,
+}
+
+impl SystemMemory {
+  /// Reads the physical memory of the system, only supported on Linux
+  #[cfg(target_os = "linux")]
+  pub fn read() -> Option<Self> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    Self::parse_meminfo(&meminfo)
+  }
+
+  #[cfg(not(target_os = "linux"))]
+  pub fn read() -> Option<Self> {
+    None
+  }
+
+  /// Parses the contents of `/proc/meminfo`
+  pub fn parse_meminfo(meminfo: &str) -> Option<Self> {
+    let read_kb = |key: &str| {
+      meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+        .and_then(|value| value.split_whitespace().next()?.parse::<u64>().ok())
+    };
+
+    let total_mb = read_kb("MemTotal")? / 1024;
+    // Kernels older than 3.14 don't report `MemAvailable`
+    let available_mb = read_kb("MemAvailable").or_else(|| Some(read_kb("MemFree")? + read_kb("Cached").unwrap_or(0)))? / 1024;
+    Some(Self { total_mb, available_mb })
+  }
+}
+
+/// How the maximum heap of the game is chosen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeapStrategy {
+  /// Always use the given heap size in MiB
+  Fixed(u64),
+  /// Pick a heap size from the version, the number of mods and the system memory
+  Auto {
+    mod_count: usize,
+    /// Upper limit in MiB chosen by the user
+    max_heap_mb: Option<u64>,
+  },
+}
+
+impl HeapStrategy {
+  /// Decides the heap size for the given version, reading the system memory if needed
+  pub fn decide(&self, manifest: &VersionManifest) -> HeapDecision {
+    match self {
+      HeapStrategy::Fixed(heap_mb) => HeapDecision { heap_mb: *heap_mb, auto: false },
+      HeapStrategy::Auto { .. } => self.decide_with_memory(manifest, SystemMemory::read()),
+    }
+  }
+
+  pub fn decide_with_memory(&self, manifest: &VersionManifest, memory: Option<SystemMemory>) -> HeapDecision {
+    let (mod_count, max_heap_mb) = match self {
+      HeapStrategy::Fixed(heap_mb) => {
+        return HeapDecision { heap_mb: *heap_mb, auto: false };
+      }
+      HeapStrategy::Auto { mod_count, max_heap_mb } => (*mod_count as u64, *max_heap_mb),
+    };
+
+    let mut heap_mb = Self::base_heap(manifest) + mod_count * HEAP_PER_MOD_MB;
+    if let Some(SystemMemory { total_mb, available_mb }) = memory {
+      let system_limit = total_mb.saturating_sub(SYSTEM_RESERVED_MB.max(total_mb / 4)).min(available_mb);
+      heap_mb = heap_mb.min(system_limit);
+    }
+
+    // Round down to half a GiB so the value reads nicely, the user's limit always wins over the minimum
+    let heap_mb = ((heap_mb / 512) * 512).max(MIN_AUTO_HEAP_MB);
+    let heap_mb = max_heap_mb.map_or(heap_mb, |max_heap_mb| heap_mb.min(max_heap_mb));
+    HeapDecision { heap_mb, auto: true }
+  }
+
+  /// Heap needed by the unmodded game, growing with the era of the version
+  fn base_heap(manifest: &VersionManifest) -> u64 {
+    let java_major = manifest.java_version.as_ref().map(|java| java.major_version).unwrap_or(8);
+    if manifest.minecraft_arguments.is_some() && manifest.arguments.is_empty() {
+      // 1.12.2 and older
+      1024
+    } else if java_major < 17 {
+      2048
+    } else {
+      // 1.18 and newer, with the taller world generation
+      3072
+    }
+  }
+}
+
+/// The heap size picked by a `HeapStrategy`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeapDecision {
+  pub heap_mb: u64,
+  /// Whether the size was chosen automatically
+  pub auto: bool,
+}
+
+impl Display for HeapDecision {
+  // `u64::is_multiple_of` is only stable since Rust 1.87
+  #[allow(clippy::manual_is_multiple_of)]
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    if self.auto {
+      write!(f, "auto: ")?;
+    }
+    if self.heap_mb % 1024 == 0 {
+      write!(f, "{} GiB", self.heap_mb / 1024)
+    } else {
+      write!(f, "{:.1} GiB", (self.heap_mb as f64) / 1024.0)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::json::manifest::java::JavaVersionInfo;
+
+  use super::*;
+
+  fn manifest(java_major: i64) -> VersionManifest {
+    serde_json::from_value(
+      serde_json::json!({
+        "id": "1.21",
+        "releaseTime": "2024-06-13T08:24:03+00:00",
+        "time": "2024-06-13T08:24:03+00:00",
+        "type": "release",
+        "arguments": { "game": [] },
+        "javaVersion": JavaVersionInfo { component: "java-runtime-delta".into(), major_version: java_major },
+      })
+    ).unwrap()
+  }
+
+  #[test]
+  fn test_parse_meminfo() {
+    let meminfo = "MemTotal:       16303756 kB\nMemFree:         1022412 kB\nMemAvailable:    9251672 kB\n";
+    assert_eq!(SystemMemory::parse_meminfo(meminfo), Some(SystemMemory { total_mb: 15921, available_mb: 9034 }));
+  }
+
+  #[test]
+  fn test_auto_heap() {
+    let manifest = manifest(21);
+    let memory = Some(SystemMemory { total_mb: 16384, available_mb: 12288 });
+
+    let vanilla = HeapStrategy::Auto { mod_count: 0, max_heap_mb: None }.decide_with_memory(&manifest, memory);
+    assert_eq!(vanilla.heap_mb, 3072);
+
+    let modded = HeapStrategy::Auto { mod_count: 200, max_heap_mb: None }.decide_with_memory(&manifest, memory);
+    assert_eq!(modded.heap_mb, 9216);
+
+    let capped = HeapStrategy::Auto { mod_count: 200, max_heap_mb: Some(4096) }.decide_with_memory(&manifest, memory);
+    assert_eq!(capped.to_string(), "auto: 4 GiB");
+
+    let low_memory = Some(SystemMemory { total_mb: 4096, available_mb: 1024 });
+    let low = HeapStrategy::Auto { mod_count: 50, max_heap_mb: None }.decide_with_memory(&manifest, low_memory);
+    assert_eq!(low.heap_mb, MIN_AUTO_HEAP_MB);
+
+    let below_minimum = HeapStrategy::Auto { mod_count: 50, max_heap_mb: Some(768) };
+    assert_eq!(below_minimum.decide_with_memory(&manifest, memory).heap_mb, 768);
+    assert_eq!(below_minimum.decide_with_memory(&manifest, low_memory).heap_mb, 768);
+  }
+}
@@ -1,21 +1,19 @@
-use std::{ collections::HashMap, env::consts::ARCH, fs::{ self, create_dir_all, File }, io::{ self }, path::{ Path, PathBuf, MAIN_SEPARATOR_STR } };
+use std::{ collections::HashMap, env, fs::{ self, create_dir_all, File }, mem::take, path::{ Path, PathBuf }, sync::atomic::{ AtomicUsize, Ordering } };
 
 use argument_substitutor::{ ArgumentSubstitutor, ArgumentSubstitutorBuilder };
 use chrono::Utc;
 use log::{ info, error, debug, warn };
-use options::{ GameOptions, LauncherOptions, ProxyOptions };
-use os_info::Type::Windows;
+use memory::HeapDecision;
+use natives::NativeJar;
+use options::{ ArgumentFileMode, GameOptions, LauncherOptions, ProxyOptions, QuickPlay };
 use process::{ GameProcess, GameProcessBuilder };
-use regex::Regex;
 use serde_json::json;
-use zip::ZipArchive;
 
 use crate::json::{
   manifest::{
     argument::ArgumentType,
     assets::{ AssetIndex, AssetIndexInfo, AssetObject },
-    library::ExtractRules,
-    rule::{ OperatingSystem, RuleFeatureType },
+    rule::{ OperatingSystem, Platform, RuleFeatureType },
     VersionManifest,
   },
   EnvironmentFeatures,
@@ -24,19 +22,23 @@ use crate::json::{
 };
 
 pub mod auth;
+pub mod accounts;
 pub mod options;
 pub mod process;
 pub mod argument_substitutor;
+pub mod jvm;
+pub mod hooks;
+pub mod natives;
+pub mod script;
+pub mod memory;
 
 mod error;
 mod token;
 
-pub use error::{ Error, UnpackAssetsError, UnpackNativesError };
+pub use error::{ Error, HookError, JvmOptionsError, UnpackAssetsError, UnpackNativesError };
 
-const DEFAULT_JRE_ARGUMENTS_32BIT: &str =
-  "-Xmx2G -XX:+UnlockExperimentalVMOptions -XX:+UseG1GC -XX:G1NewSizePercent=20 -XX:G1ReservePercent=20 -XX:MaxGCPauseMillis=50 -XX:G1HeapRegionSize=32M";
-const DEFAULT_JRE_ARGUMENTS_64BIT: &str =
-  "-Xmx2G -XX:+UnlockExperimentalVMOptions -XX:+UseG1GC -XX:G1NewSizePercent=20 -XX:G1ReservePercent=20 -XX:MaxGCPauseMillis=50 -XX:G1HeapRegionSize=32M";
+/// Number of argument files written by this process, used to name them
+static ARGUMENT_FILES: AtomicUsize = AtomicUsize::new(0);
 
 pub struct GameBootstrap {
   pub options: GameOptions,
@@ -53,12 +55,25 @@ impl GameBootstrap {
   }
 
   fn get_assets_dir(&self) -> PathBuf {
-    self.options.game_dir.join("assets")
+    self.options.shared_dir().join("assets")
   }
 
-  fn is_win_ten(&self) -> bool {
-    let os = os_info::get();
-    os.os_type() == Windows && os.edition().is_some_and(|edition| edition.contains("Windows 10"))
+  /// Returns the heap size chosen by the `heap_strategy` option for the given version, if any
+  pub fn heap_decision(&self, manifest: &VersionManifest) -> Option<HeapDecision> {
+    self.options.heap_strategy.as_ref().map(|strategy| strategy.decide(manifest))
+  }
+
+  /// Whether the target platform is Windows 10. Windows 11 still reports version 10.0, from build 22000.
+  fn is_win_ten(platform: &Platform) -> bool {
+    if platform.os != OperatingSystem::Windows {
+      return false;
+    }
+    let version: Vec<u64> = platform.os_version.split('.').map_while(|part| part.parse().ok()).collect();
+    match version.as_slice() {
+      [10, 0, build, ..] => *build < 22000,
+      [10, ..] => true,
+      _ => false,
+    }
   }
 }
 
@@ -107,16 +122,16 @@ impl GameBootstrap {
   /// }
   /// ```
   pub fn prepare_launch(&mut self, manifest: &VersionManifest) -> Result<GameProcessBuilder, Error> {
-    let os = OperatingSystem::get_current_platform();
+    let platform = &self.options.platform;
     let game_dir = &self.options.game_dir;
     let env_features = &self.env_features;
     info!("Launching game");
 
     // Prepare natives
-    if let Err(err) = self.unpack_natives(manifest) {
+    let natives_dir = self.unpack_natives(manifest).map_err(|err| {
       error!("Couldn't unpack natives! {err}");
-      return Err(Error::UnpackNatives(err));
-    }
+      Error::UnpackNatives(err)
+    })?;
 
     let game_assets_dir = self.reconstruct_assets(manifest).map_err(|err| {
       error!("Couldn't unpack assets! {err}");
@@ -139,18 +154,38 @@ impl GameBootstrap {
     let server_resource_packs_dir = game_dir.join("server-resource-packs");
     create_dir_all(server_resource_packs_dir)?;
 
+    // Versions without `javaVersion` predate it and run on Java 8
+    let java_major = manifest.java_version.as_ref().map(|java| java.major_version).unwrap_or(8);
     let mut game_process_builder = GameProcessBuilder::new();
     game_process_builder.with_java_path(&self.options.java_path);
     game_process_builder.directory(game_dir);
+    game_process_builder.with_envs(&self.options.env_vars).clear_env(self.options.clear_env);
+    for wrapper in &self.options.wrapper_commands {
+      game_process_builder.with_wrapper(wrapper.iter().collect());
+    }
 
     if let Some(jvm_args) = &self.options.jvm_args {
       game_process_builder.with_arguments(jvm_args.iter().collect());
     } else {
-      let args = if ARCH == "x86_64" { DEFAULT_JRE_ARGUMENTS_64BIT } else { DEFAULT_JRE_ARGUMENTS_32BIT };
-      game_process_builder.with_arguments(args.split(' ').collect());
+      let mut jvm_options = self.options.jvm_options.clone().unwrap_or_default();
+      if let Some(decision) = self.heap_decision(manifest) {
+        info!("Using heap size {decision}");
+        jvm_options.max_heap_mb.replace(decision.heap_mb);
+        jvm_options.min_heap_mb = jvm_options.min_heap_mb.map(|min| min.min(decision.heap_mb));
+      }
+      game_process_builder.with_arguments(jvm_options.arguments(java_major, platform.arch.is_64bit())?);
     }
 
-    let substitutor = self.create_arguments_substitutor(manifest, &game_assets_dir)?;
+    let substitutor = self.create_arguments_substitutor(manifest, &game_assets_dir, &natives_dir)?;
+
+    // Hooks may receive the access token, keep it out of the logs
+    let access_token = self.options.authentication.access_token();
+    for hook in &self.options.pre_launch_hooks {
+      game_process_builder.with_pre_launch_hook(hook.substitute(&substitutor).redact(access_token).in_directory(game_dir));
+    }
+    for hook in &self.options.post_exit_hooks {
+      game_process_builder.with_post_exit_hook(hook.substitute(&substitutor).redact(access_token).in_directory(game_dir));
+    }
 
     // Add JVM args
     if !manifest.arguments.is_empty() {
@@ -158,7 +193,7 @@ impl GameBootstrap {
         game_process_builder.with_arguments(
           jvm_arguments
             .iter()
-            .filter_map(|v| v.apply(env_features))
+            .filter_map(|v| v.apply(env_features, platform))
             .flatten()
             .map(|arg| substitutor.substitute(arg))
             .collect()
@@ -166,12 +201,12 @@ impl GameBootstrap {
       }
     } else if manifest.minecraft_arguments.is_some() {
       // Manifest uses old format
-      if os == OperatingSystem::Windows {
+      if platform.os == OperatingSystem::Windows {
         game_process_builder.with_argument("-XX:HeapDumpPath=MojangTricksIntelDriversForPerformance_javaw.exe_minecraft.exe.heapdump");
-        if self.is_win_ten() {
+        if Self::is_win_ten(platform) {
           game_process_builder.with_arguments(vec!["-Dos.name=Windows 10", "-Dos.version=10.0"]);
         }
-      } else if os == OperatingSystem::Osx {
+      } else if platform.os == OperatingSystem::Osx {
         game_process_builder.with_arguments(substitutor.substitute_all(vec!["-Xdock:icon=${asset=icons/minecraft.icns}", "-Xdock:name=Minecraft"]));
       }
 
@@ -189,6 +224,8 @@ impl GameBootstrap {
       );
     }
 
+    Self::apply_argument_file(self.options.argument_file, &mut game_process_builder, java_major, &env::temp_dir())?;
+
     game_process_builder.with_argument(manifest.get_main_class());
 
     info!("Half command: {}", game_process_builder.get_args().join(" "));
@@ -197,7 +234,7 @@ impl GameBootstrap {
         game_process_builder.with_arguments(
           arguments
             .iter()
-            .filter_map(|v| v.apply(env_features))
+            .filter_map(|v| v.apply(env_features, platform))
             .flatten()
             .map(|arg| substitutor.substitute(arg))
             .collect()
@@ -206,7 +243,7 @@ impl GameBootstrap {
     } else if let Some(minecraft_arguments) = &manifest.minecraft_arguments {
       game_process_builder.with_arguments(
         minecraft_arguments
-          .split(' ')
+          .split_whitespace()
           .map(|arg| substitutor.substitute(arg))
           .collect()
       );
@@ -221,6 +258,17 @@ impl GameBootstrap {
       }
     }
 
+    // Versions without quick play arguments can only join servers directly
+    if let Some(quick_play) = &self.options.quick_play {
+      if !Self::supports_quick_play(manifest) {
+        if let QuickPlay::Multiplayer { host, port } = quick_play {
+          game_process_builder.with_arguments(vec!["--server", host, "--port", &port.unwrap_or(25565).to_string()]);
+        } else {
+          warn!("Version {} doesn't support quick play, ignoring {:?}", manifest.id, quick_play);
+        }
+      }
+    }
+
     if let ProxyOptions::Proxy { host, port, username, password } = &self.options.proxy {
       game_process_builder.with_arguments(vec!["--proxyHost", host, "--proxyPort", &port.to_string()]);
 
@@ -236,66 +284,82 @@ impl GameBootstrap {
     // Print args for debug purposes
     {
       // Remove token from args
-      let args_vec = game_process_builder.get_args();
-      let mut args = args_vec.join(" ");
+      let mut command = game_process_builder.display_command()?;
       if let Some(token) = &self.options.authentication.access_token {
-        args = args.replace(token, "?????");
+        command = command.replace(token, "?????");
       }
-      debug!("Running {} {}", &self.options.java_path.display(), args);
+      debug!("Running {}", command);
+    }
 
-      let regex = Regex::new(r"\$\{.+\}")?;
-      args_vec
-        .iter()
-        .filter_map(|arg| regex.find(arg))
-        .for_each(|arg| debug!("Unresolved variable - {:?}", arg.as_str()));
+    let unresolved = substitutor.take_unresolved();
+    for variable in &unresolved {
+      warn!("Unresolved variable - {variable}");
+    }
+    if self.options.strict_arguments && !unresolved.is_empty() {
+      error!("Aborting launch; {} unresolved variable(s)", unresolved.len());
+      return Err(Error::UnresolvedVariables(unresolved));
     }
+    game_process_builder.unresolved_variables = unresolved;
 
     Ok(game_process_builder)
   }
 
-  fn unpack_natives(&self, manifest: &VersionManifest) -> Result<(), UnpackNativesError> {
-    let os = OperatingSystem::get_current_platform();
-    let natives_dir = &self.options.natives_dir;
-    info!("Unpacking natives to {}", natives_dir.display());
-    create_dir_all(natives_dir).map_err(UnpackNativesError::CreateNativesFolder)?;
-
-    let libs = manifest.get_relevant_libraries(&self.env_features);
-
-    fn unpack_native(natives_dir: &Path, mut zip_archive: ZipArchive<File>, extract_rules: Option<&ExtractRules>) -> Result<(), UnpackNativesError> {
-      for i in 0..zip_archive.len() {
-        let mut file = zip_archive.by_index(i)?;
-        let file_zip_path = file.enclosed_name().unwrap().to_owned();
-        if let Some(extract_rules) = extract_rules {
-          if !extract_rules.should_extract(&file_zip_path) {
-            continue;
-          }
-        }
-
-        let output_file = natives_dir.join(file_zip_path);
-        if let Some(parent) = output_file.parent() {
-          create_dir_all(parent).map_err(UnpackNativesError::UnpackNative)?;
-        }
-        if !file.is_dir() {
-          let mut output_file = File::create(output_file).map_err(UnpackNativesError::UnpackNative)?;
-          io::copy(&mut file, &mut output_file).map_err(UnpackNativesError::UnpackNative)?;
-        }
+  /// Moves the JVM arguments collected so far (or just the classpath) into an `@argfile` in `dir`.
+  /// The file is unique to this launch, so concurrent launches of a version don't overwrite each other's.
+  fn apply_argument_file(mode: ArgumentFileMode, builder: &mut GameProcessBuilder, java_major: i64, dir: &Path) -> Result<(), Error> {
+    if java_major < 9 {
+      if mode != ArgumentFileMode::Inline {
+        info!("Java {java_major} doesn't support argument files, passing arguments inline");
       }
-      Ok(())
+      return Ok(());
     }
 
-    for lib in libs {
-      if let Some(Some(native_id)) = lib.get_artifact_classifier(&os) {
-        let file = &self.options.game_dir.join("libraries").join(lib.get_artifact_path(Some(native_id.clone())).replace('/', MAIN_SEPARATOR_STR));
-        let file = File::open(file).map_err(UnpackNativesError::ReadNative)?;
-        let zip_file = ZipArchive::new(file)?;
-        let extract_rules = lib.extract.as_ref();
-        let _ = unpack_native(natives_dir, zip_file, extract_rules); // Ignore errors
+    let (position, file_arguments) = match mode {
+      ArgumentFileMode::Inline => {
+        return Ok(());
       }
-    }
+      ArgumentFileMode::Classpath => {
+        let Some(position) = builder.arguments
+          .iter()
+          .position(|arg| matches!(arg.as_str(), "-cp" | "-classpath" | "--class-path")) else {
+          warn!("No classpath argument found, passing arguments inline");
+          return Ok(());
+        };
+        let end = (position + 2).min(builder.arguments.len());
+        (position, builder.arguments.drain(position..end).collect::<Vec<_>>())
+      }
+      ArgumentFileMode::JvmArguments => (0, take(&mut builder.arguments)),
+    };
 
+    let launch = ARGUMENT_FILES.fetch_add(1, Ordering::Relaxed);
+    let argument_file = dir.join(format!("launch-arguments-{}-{launch}.txt", std::process::id()));
+    let contents = file_arguments
+      .iter()
+      .map(|arg| script::quote_argument_file(arg))
+      .collect::<Vec<_>>()
+      .join("\n");
+    fs::write(&argument_file, contents)?;
+    info!("Wrote {} argument(s) to {}", file_arguments.len(), argument_file.display());
+
+    builder.arguments.insert(position, format!("@{}", argument_file.display()));
+    builder.argument_file.replace(argument_file);
     Ok(())
   }
 
+  fn unpack_natives(&self, manifest: &VersionManifest) -> Result<PathBuf, UnpackNativesError> {
+    let Platform { os, arch, .. } = &self.options.platform;
+    let libraries_dir = self.options.shared_dir().join("libraries");
+    let natives_root = &self.options.natives_dir;
+    create_dir_all(natives_root).map_err(UnpackNativesError::CreateNativesFolder)?;
+
+    let jars: Vec<_> = manifest
+      .get_relevant_libraries(&self.env_features, &self.options.platform)
+      .into_iter()
+      .filter_map(|lib| NativeJar::from_library(lib, os, arch, &libraries_dir))
+      .collect();
+    natives::extract_natives(natives_root, &manifest.id.to_string(), &jars)
+  }
+
   /// Reconstructs the assets based on the provided version manifest.
   ///
   /// This function attempts to reconstruct the assets by reading the asset index
@@ -321,7 +385,7 @@ impl GameBootstrap {
   fn reconstruct_assets(&self, manifest: &VersionManifest) -> Result<PathBuf, UnpackAssetsError> {
     let asset_index_info = manifest.asset_index.as_ref().ok_or(UnpackAssetsError::NoAssetIndex)?;
 
-    let assets_dir = self.options.game_dir.join("assets");
+    let assets_dir = self.get_assets_dir();
     let indexes_dir = assets_dir.join("indexes");
     let objects_dir = assets_dir.join("objects");
 
@@ -369,24 +433,23 @@ impl GameBootstrap {
     Ok(virtual_dir)
   }
 
-  fn create_arguments_substitutor(&self, manifest: &VersionManifest, game_assets_dir: &Path) -> Result<ArgumentSubstitutor, Error> {
+  fn create_arguments_substitutor(&self, manifest: &VersionManifest, game_assets_dir: &Path, natives_dir: &Path) -> Result<ArgumentSubstitutor, Error> {
     let asset_index_info = manifest.asset_index.as_ref();
     let mut substitutor = ArgumentSubstitutorBuilder::new();
 
-    let classpath_separator = if OperatingSystem::get_current_platform() == OperatingSystem::Windows { ";" } else { ":" };
+    let classpath_separator = self.options.platform.classpath_separator();
     let version_id = manifest.id.to_string();
     let version_name = self.options.version_name.as_ref().unwrap_or(&version_id);
     let game_dir = &self.options.game_dir;
 
     let classpath = self.construct_classpath(manifest)?;
     let assets_dir = self.get_assets_dir();
-    let libraries_dir = game_dir.join("libraries");
-    let natives_dir = &self.options.natives_dir;
+    let libraries_dir = self.options.shared_dir().join("libraries");
 
     let launcher_opts = self.options.launcher_options.as_ref();
 
     let jar_id = manifest.get_jar().to_string();
-    let jar_path = game_dir.join("versions").join(&jar_id).join(format!("{}.jar", &jar_id));
+    let jar_path = self.options.shared_dir().join("versions").join(&jar_id).join(format!("{}.jar", &jar_id));
 
     let asset_index_substitutions = {
       let mut map = HashMap::new();
@@ -433,6 +496,20 @@ impl GameBootstrap {
       substitutor.add("resolution_height", "");
     }
 
+    let quick_play = self.options.quick_play.as_ref();
+    let quick_play_path = self.options.quick_play_path.as_ref().and_then(|path| path.to_str());
+    substitutor
+      .add("quickPlayPath", quick_play_path.unwrap_or_default())
+      .add("quickPlaySingleplayer", match quick_play {
+        Some(QuickPlay::Singleplayer(world)) => world,
+        _ => "",
+      })
+      .add("quickPlayMultiplayer", quick_play.and_then(QuickPlay::server_address).unwrap_or_default())
+      .add("quickPlayRealms", match quick_play {
+        Some(QuickPlay::Realms(realm)) => realm,
+        _ => "",
+      });
+
     substitutor.add("language", "en-us").add_all(asset_index_substitutions);
 
     if let Some(LauncherOptions { launcher_name, launcher_version }) = launcher_opts {
@@ -461,9 +538,8 @@ impl GameBootstrap {
   }
 
   fn construct_classpath(&self, manifest: &VersionManifest) -> Result<String, Error> {
-    let os = OperatingSystem::get_current_platform();
-    let separator = if os == OperatingSystem::Windows { ";" } else { ":" };
-    let classpath = manifest.get_classpath(&os, &self.options.game_dir, &self.env_features);
+    let platform = &self.options.platform;
+    let classpath = manifest.get_classpath(platform, self.options.shared_dir(), &self.env_features);
 
     let mut vec = vec![];
     for path in &classpath {
@@ -476,7 +552,13 @@ impl GameBootstrap {
         return Err(Error::InvalidClasspathPath(path.to_path_buf()));
       }
     }
-    Ok(vec.join(separator))
+    Ok(vec.join(platform.classpath_separator()))
+  }
+
+  fn supports_quick_play(manifest: &VersionManifest) -> bool {
+    manifest.arguments
+      .get(&ArgumentType::Game)
+      .is_some_and(|args| args.iter().any(|arg| arg.value().iter().any(|value| value.contains("${quickPlayMultiplayer}"))))
   }
 
   fn get_asset_index(&self, asset_index_info: &AssetIndexInfo) -> Result<AssetIndex, Box<dyn std::error::Error>> {
@@ -487,3 +569,137 @@ impl GameBootstrap {
     Ok(serde_json::from_reader(file)?)
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use std::env::temp_dir;
+
+  use options::GameOptionsBuilder;
+
+  use crate::json::manifest::rule::Architecture;
+
+  use super::*;
+
+  /// A manifest with the quick play arguments of 1.20+, or the `minecraftArguments` of older versions if `legacy`
+  fn quick_play_manifest(legacy: bool) -> VersionManifest {
+    let quick_play = |feature: &str, argument: &str| {
+      json!({ "rules": [{ "action": "allow", "features": { feature: true } }], "value": [format!("--{argument}"), format!("${{{argument}}}")] })
+    };
+    let mut manifest = json!({
+      "id": "quick-play",
+      "assetIndex": { "id": "quick-play", "sha1": "0000000000000000000000000000000000000000", "size": 0, "totalSize": 0, "url": "" },
+      "mainClass": "net.minecraft.client.main.Main",
+      "releaseTime": "2023-06-12T13:25:51+00:00",
+      "time": "2023-06-12T13:25:51+00:00",
+      "type": "release"
+    });
+    if legacy {
+      manifest["minecraftArguments"] = json!("--username ${auth_player_name} --version ${version_name}");
+    } else {
+      manifest["arguments"] = json!({
+        "game": [
+          "--username", "${auth_player_name}",
+          { "rules": [{ "action": "allow", "features": { "has_quick_plays_support": true } }], "value": ["--quickPlayPath", "${quickPlayPath}"] },
+          quick_play("is_quick_play_singleplayer", "quickPlaySingleplayer"),
+          quick_play("is_quick_play_multiplayer", "quickPlayMultiplayer"),
+          quick_play("is_quick_play_realms", "quickPlayRealms")
+        ],
+        "jvm": ["-cp", "${classpath}"]
+      });
+    }
+    serde_json::from_value(manifest).unwrap()
+  }
+
+  /// Game arguments (after the main class) prepared for the manifest with `quick_play`
+  fn quick_play_arguments(dir: &Path, manifest: &VersionManifest, quick_play: QuickPlay) -> Vec<String> {
+    let jar = dir.join("versions").join("quick-play").join("quick-play.jar");
+    create_dir_all(jar.parent().unwrap()).unwrap();
+    fs::write(jar, "").unwrap();
+    let options = GameOptionsBuilder::default()
+      .java_path(PathBuf::from("java"))
+      .game_dir(dir.to_path_buf())
+      .natives_dir(dir.join("natives"))
+      .authentication(auth::UserAuthentication::offline("Player"))
+      .quick_play(quick_play)
+      .quick_play_path(dir.join("quickPlay").join("log.json"))
+      .build()
+      .unwrap();
+    let arguments = GameBootstrap::new(options).prepare_launch(manifest).unwrap().arguments;
+    let main_class = arguments.iter().position(|arg| arg == "net.minecraft.client.main.Main").unwrap();
+    arguments[main_class + 1..].to_vec()
+  }
+
+  #[test]
+  fn test_is_win_ten() {
+    let windows = |version: &str| Platform::new(OperatingSystem::Windows, Architecture::X64, version);
+    assert!(GameBootstrap::is_win_ten(&windows("10.0.19045")));
+    assert!(GameBootstrap::is_win_ten(&windows("10.0")));
+    assert!(!GameBootstrap::is_win_ten(&windows("10.0.22631")));
+    assert!(!GameBootstrap::is_win_ten(&windows("6.1.7601")));
+    assert!(!GameBootstrap::is_win_ten(&Platform::new(OperatingSystem::Linux, Architecture::X64, "10.0")));
+  }
+
+  #[test]
+  fn test_quick_play() {
+    let dir = temp_dir().join(format!("mlc-quick-play-{}", std::process::id()));
+    let (modern, legacy) = (quick_play_manifest(false), quick_play_manifest(true));
+    assert!(GameBootstrap::supports_quick_play(&modern));
+    assert!(!GameBootstrap::supports_quick_play(&legacy));
+
+    let quick_play_path = dir.join("quickPlay").join("log.json").display().to_string();
+    let arguments = quick_play_arguments(&dir, &modern, QuickPlay::Singleplayer("New World".to_string()));
+    assert_eq!(arguments, vec!["--username", "Player", "--quickPlayPath", &quick_play_path, "--quickPlaySingleplayer", "New World"]);
+    let arguments = quick_play_arguments(&dir, &modern, QuickPlay::Multiplayer { host: "mc.example.com".to_string(), port: Some(25566) });
+    assert_eq!(arguments[4..], ["--quickPlayMultiplayer", "mc.example.com:25566"]);
+    let arguments = quick_play_arguments(&dir, &modern, QuickPlay::Realms("1234".to_string()));
+    assert_eq!(arguments[4..], ["--quickPlayRealms", "1234"]);
+
+    // Older versions can only join a server, with the default port if none is given
+    let arguments = quick_play_arguments(&dir, &legacy, QuickPlay::Multiplayer { host: "mc.example.com".to_string(), port: None });
+    assert_eq!(arguments, vec!["--username", "Player", "--version", "quick-play", "--server", "mc.example.com", "--port", "25565"]);
+    let arguments = quick_play_arguments(&dir, &legacy, QuickPlay::Singleplayer("New World".to_string()));
+    assert_eq!(arguments, vec!["--username", "Player", "--version", "quick-play"]);
+    let arguments = quick_play_arguments(&dir, &legacy, QuickPlay::Realms("1234".to_string()));
+    assert_eq!(arguments, vec!["--username", "Player", "--version", "quick-play"]);
+
+    let _ = fs::remove_dir_all(dir);
+  }
+
+  #[test]
+  fn test_apply_argument_file() {
+    let dir = temp_dir().join(format!("mlc-argfile-{}", std::process::id()));
+    create_dir_all(&dir).unwrap();
+    let new_builder = || {
+      let mut builder = GameProcessBuilder::new();
+      builder.with_java_path(Path::new("java")).with_arguments(vec!["-Xmx2G", "-cp", "a.jar:b \"c\".jar"]);
+      builder
+    };
+
+    let mut builder = new_builder();
+    GameBootstrap::apply_argument_file(ArgumentFileMode::Inline, &mut builder, 17, &dir).unwrap();
+    GameBootstrap::apply_argument_file(ArgumentFileMode::Classpath, &mut builder, 8, &dir).unwrap();
+    assert_eq!(builder.arguments, new_builder().arguments);
+    assert!(builder.argument_file.is_none());
+
+    GameBootstrap::apply_argument_file(ArgumentFileMode::Classpath, &mut builder, 17, &dir).unwrap();
+    let argument_file = builder.argument_file.clone().unwrap();
+    assert!(argument_file.starts_with(&dir));
+    builder.with_argument("net.minecraft.client.main.Main");
+    assert_eq!(fs::read_to_string(&argument_file).unwrap(), "\"-cp\"\n\"a.jar:b \\\"c\\\".jar\"");
+    let argfile = format!("@{}", argument_file.display());
+    assert_eq!(builder.get_command_line().unwrap(), vec!["java", "-Xmx2G", &argfile, "net.minecraft.client.main.Main"]);
+
+    let mut builder = new_builder();
+    GameBootstrap::apply_argument_file(ArgumentFileMode::JvmArguments, &mut builder, 17, &dir).unwrap();
+    builder.with_argument("net.minecraft.client.main.Main");
+    // Every launch gets its own file
+    assert_ne!(builder.argument_file.as_ref(), Some(&argument_file));
+    let argument_file = builder.argument_file.clone().unwrap();
+    let argfile = format!("@{}", argument_file.display());
+    assert_eq!(fs::read_to_string(&argument_file).unwrap().lines().next(), Some("\"-Xmx2G\""));
+    assert_eq!(fs::read_to_string(&argument_file).unwrap().lines().count(), 3);
+    assert_eq!(builder.get_command_line().unwrap(), vec!["java", &argfile, "net.minecraft.client.main.Main"]);
+
+    let _ = fs::remove_dir_all(dir);
+  }
+}
@@ -0,0 +1,199 @@
+use std::{ fs::{ self, create_dir_all, File }, io, path::{ Path, PathBuf } };
+
+use log::{ debug, info, warn };
+use sha1::{ Digest, Sha1 };
+use zip::ZipArchive;
+
+use crate::json::{ manifest::{ library::{ ExtractRules, Library }, rule::{ Architecture, OperatingSystem } }, Sha1Sum };
+
+use super::error::UnpackNativesError;
+
+/// File written once a natives directory is fully extracted
+const COMPLETE_MARKER: &str = ".complete";
+/// Length of the hash suffix of a natives directory
+const KEY_LENGTH: usize = 12;
+const NATIVE_EXTENSIONS: &[&str] = &["so", "dll", "dylib", "jnilib"];
+
+/// A jar whose native libraries must be extracted before launching
+#[derive(Debug, Clone)]
+pub struct NativeJar {
+  pub path: PathBuf,
+  pub kind: NativeJarKind,
+}
+
+#[derive(Debug, Clone)]
+pub enum NativeJarKind {
+  /// Legacy library with a `natives` map, extracted as-is apart from the excluded paths
+  Classified(Option<ExtractRules>),
+  /// LWJGL 3 `natives-*` classpath jar, only the libraries are extracted to the root of the directory
+  Classpath,
+}
+
+impl NativeJar {
+  /// Returns the native jar of `library`, if it has one for the given OS
+  pub fn from_library(library: &Library, os: &OperatingSystem, arch: &Architecture, libraries_dir: &Path) -> Option<Self> {
+    if let Some(Some(classifier)) = library.get_artifact_classifier(os, arch) {
+      let mut artifact = library.name.clone();
+      artifact.classifier = Some(classifier);
+      return Some(Self { path: artifact.get_local_path(libraries_dir), kind: NativeJarKind::Classified(library.extract.clone()) });
+    }
+
+    let is_classpath_native = library.natives.is_empty() && library.name.classifier.as_ref().is_some_and(|classifier| classifier.starts_with("natives-"));
+    is_classpath_native.then(|| Self { path: library.name.get_local_path(libraries_dir), kind: NativeJarKind::Classpath })
+  }
+
+  fn extract(&self, target_dir: &Path) -> Result<(), UnpackNativesError> {
+    let file = File::open(&self.path).map_err(UnpackNativesError::ReadNative)?;
+    let mut zip_archive = ZipArchive::new(file)?;
+
+    for i in 0..zip_archive.len() {
+      let mut file = zip_archive.by_index(i)?;
+      let zip_path = file.enclosed_name().ok_or_else(|| UnpackNativesError::UnsafePath(file.name().to_string()))?;
+
+      let output_file = match &self.kind {
+        NativeJarKind::Classified(extract_rules) => {
+          if extract_rules.as_ref().is_some_and(|rules| !rules.should_extract(&zip_path)) {
+            continue;
+          }
+          target_dir.join(zip_path)
+        }
+        NativeJarKind::Classpath => {
+          let is_native = zip_path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| NATIVE_EXTENSIONS.contains(&ext));
+          if file.is_dir() || zip_path.starts_with("META-INF") || !is_native {
+            continue;
+          }
+          // LWJGL looks up its libraries directly in `java.library.path`
+          let Some(file_name) = zip_path.file_name() else {
+            continue;
+          };
+          target_dir.join(file_name)
+        }
+      };
+
+      if file.is_dir() {
+        create_dir_all(&output_file).map_err(UnpackNativesError::UnpackNative)?;
+        continue;
+      }
+      if let Some(parent) = output_file.parent() {
+        create_dir_all(parent).map_err(UnpackNativesError::UnpackNative)?;
+      }
+      let mut output_file = File::create(output_file).map_err(UnpackNativesError::UnpackNative)?;
+      io::copy(&mut file, &mut output_file).map_err(UnpackNativesError::UnpackNative)?;
+    }
+    Ok(())
+  }
+}
+
+/// Extracts native jars into `<natives_root>/<version_id>-<hash>`, where the hash is built from the SHA-1 of every jar.
+///
+/// Extraction is skipped when the directory is already complete, and directories left by previous natives of
+/// the same version are removed.
+///
+/// # Errors
+/// Returns an `UnpackNativesError` if a jar can't be read, or contains a path escaping the natives directory.
+pub fn extract_natives(natives_root: &Path, version_id: &str, jars: &[NativeJar]) -> Result<PathBuf, UnpackNativesError> {
+  let key = natives_key(jars)?;
+  let natives_dir = natives_root.join(format!("{version_id}-{key}"));
+
+  if natives_dir.join(COMPLETE_MARKER).is_file() {
+    debug!("Natives in {} are up to date", natives_dir.display());
+  } else {
+    info!("Unpacking natives to {}", natives_dir.display());
+    if natives_dir.exists() {
+      fs::remove_dir_all(&natives_dir).map_err(UnpackNativesError::CreateNativesFolder)?;
+    }
+    create_dir_all(&natives_dir).map_err(UnpackNativesError::CreateNativesFolder)?;
+    for jar in jars {
+      jar.extract(&natives_dir)?;
+    }
+    fs::write(natives_dir.join(COMPLETE_MARKER), &key).map_err(UnpackNativesError::UnpackNative)?;
+  }
+
+  clean_stale_natives(natives_root, version_id, &natives_dir);
+  Ok(natives_dir)
+}
+
+fn natives_key(jars: &[NativeJar]) -> Result<String, UnpackNativesError> {
+  let mut hasher = Sha1::new();
+  for jar in jars {
+    let mut file = File::open(&jar.path).map_err(UnpackNativesError::ReadNative)?;
+    let checksum = Sha1Sum::from_reader(&mut file).map_err(UnpackNativesError::ReadNative)?;
+    hasher.update(checksum.as_slice());
+  }
+  let mut key = Sha1Sum::from(hasher).to_string();
+  key.truncate(KEY_LENGTH);
+  Ok(key)
+}
+
+/// Removes the natives directories of `version_id` other than `current`. Failures are only logged,
+/// since another running instance may still hold the files.
+fn clean_stale_natives(natives_root: &Path, version_id: &str, current: &Path) {
+  let Ok(entries) = fs::read_dir(natives_root) else {
+    return;
+  };
+  let prefix = format!("{version_id}-");
+  for entry in entries.flatten() {
+    let path = entry.path();
+    let is_stale = entry
+      .file_name()
+      .to_str()
+      .and_then(|name| name.strip_prefix(&prefix))
+      .is_some_and(|key| key.len() == KEY_LENGTH && key.chars().all(|c| c.is_ascii_hexdigit()));
+    if is_stale && path != current && path.is_dir() {
+      debug!("Removing stale natives {}", path.display());
+      if let Err(err) = fs::remove_dir_all(&path) {
+        warn!("Couldn't remove stale natives {}: {err}", path.display());
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::Write;
+
+  use zip::{ write::SimpleFileOptions, ZipWriter };
+
+  use super::*;
+
+  fn write_jar(path: &Path, entries: &[(&str, &[u8])]) {
+    let mut zip = ZipWriter::new(File::create(path).unwrap());
+    for (name, contents) in entries {
+      zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+      zip.write_all(contents).unwrap();
+    }
+    zip.finish().unwrap();
+  }
+
+  #[test]
+  fn test_extract_natives() {
+    let root = std::env::temp_dir().join(format!("mlc-natives-{}", std::process::id()));
+    let natives_root = root.join("natives");
+    create_dir_all(&natives_root).unwrap();
+
+    let jar_path = root.join("lwjgl-natives-linux.jar");
+    write_jar(&jar_path, &[("META-INF/MANIFEST.MF", b""), ("linux/x64/org/lwjgl/liblwjgl.so", b"v1")]);
+    let jars = vec![NativeJar { path: jar_path.clone(), kind: NativeJarKind::Classpath }];
+
+    let first = extract_natives(&natives_root, "1.21", &jars).unwrap();
+    assert_eq!(fs::read(first.join("liblwjgl.so")).unwrap(), b"v1");
+    assert!(!first.join("META-INF").exists());
+    assert_eq!(extract_natives(&natives_root, "1.21", &jars).unwrap(), first);
+
+    write_jar(&jar_path, &[("linux/x64/org/lwjgl/liblwjgl.so", b"v2")]);
+    let second = extract_natives(&natives_root, "1.21", &jars).unwrap();
+    assert_ne!(first, second);
+    assert!(!first.exists());
+
+    let evil_path = root.join("evil.jar");
+    write_jar(&evil_path, &[("../escaped.dll", b"")]);
+    let evil = vec![NativeJar { path: evil_path, kind: NativeJarKind::Classified(None) }];
+    assert!(matches!(extract_natives(&natives_root, "1.8.9", &evil), Err(UnpackNativesError::UnsafePath(_))));
+    assert!(!natives_root.join("escaped.dll").exists());
+
+    let _ = fs::remove_dir_all(root);
+  }
+}
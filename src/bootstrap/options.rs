@@ -1,10 +1,10 @@
-use std::{ path::PathBuf, collections::HashMap, fmt::Debug };
+use std::{ path::{ Path, PathBuf }, collections::HashMap, fmt::Debug };
 
 use derive_builder::Builder;
 use serde_json::json;
 
-use crate::json::{ manifest::rule::RuleFeatureType, EnvironmentFeatures };
-use super::auth::UserAuthentication;
+use crate::json::{ manifest::rule::{ Platform, RuleFeatureType }, EnvironmentFeatures };
+use super::{ auth::UserAuthentication, hooks::HookCommand, jvm::JvmOptions, memory::HeapStrategy };
 
 #[derive(Debug, Clone)]
 pub struct LauncherOptions {
@@ -43,6 +43,53 @@ impl ProxyOptions {
   }
 }
 
+/// How JVM arguments are passed to Java 9+, whose `@argfiles` avoid command line length limits
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ArgumentFileMode {
+  /// Pass every argument on the command line
+  #[default]
+  Inline,
+  /// Write only the classpath to an argument file
+  Classpath,
+  /// Write every JVM argument to an argument file
+  JvmArguments,
+}
+
+/// World or server to join directly once the game has started
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuickPlay {
+  /// Name of the singleplayer world folder
+  Singleplayer(String),
+  Multiplayer {
+    host: String,
+    port: Option<u16>,
+  },
+  /// Id of the realm
+  Realms(String),
+}
+
+impl QuickPlay {
+  pub fn feature(&self) -> RuleFeatureType {
+    match self {
+      QuickPlay::Singleplayer(_) => RuleFeatureType::IsQuickPlaySingleplayer,
+      QuickPlay::Multiplayer { .. } => RuleFeatureType::IsQuickPlayMultiplayer,
+      QuickPlay::Realms(_) => RuleFeatureType::IsQuickPlayRealms,
+    }
+  }
+
+  /// The server address in the `host:port` form expected by `--quickPlayMultiplayer`
+  pub fn server_address(&self) -> Option<String> {
+    if let QuickPlay::Multiplayer { host, port } = self {
+      Some(match port {
+        Some(port) => format!("{host}:{port}"),
+        None => host.clone(),
+      })
+    } else {
+      None
+    }
+  }
+}
+
 #[derive(Debug, Clone, Builder)]
 #[builder(pattern = "owned", setter(strip_option))]
 pub struct GameOptions {
@@ -50,8 +97,14 @@ pub struct GameOptions {
   pub java_path: PathBuf,
   /// Path to the game directory
   pub game_dir: PathBuf,
-  /// Path to the natives directory
+  /// Root of the natives directories, natives are extracted into a `<version>-<hash>` subdirectory
   pub natives_dir: PathBuf,
+  #[builder(default)]
+  /// Directory holding the `libraries`, `assets` and `versions` shared between instances, defaults to `game_dir`
+  pub shared_dir: Option<PathBuf>,
+  #[builder(default)]
+  /// Platform rules, natives and the classpath are resolved for, the running system by default
+  pub platform: Platform,
 
   /// The user authentication
   pub authentication: UserAuthentication,
@@ -64,13 +117,47 @@ pub struct GameOptions {
   #[builder(default)]
   pub proxy: ProxyOptions,
 
+  #[builder(default)]
+  /// World, server or realm to join on startup
+  pub quick_play: Option<QuickPlay>,
+  #[builder(default)]
+  /// File where the game logs its quick play sessions
+  pub quick_play_path: Option<PathBuf>,
+
   #[builder(default)]
   pub launcher_options: Option<LauncherOptions>,
 
+  #[builder(default)]
+  /// Heap, garbage collector and extra flags applied to the JVM (ignored if `jvm_args` is set)
+  pub jvm_options: Option<JvmOptions>,
+  #[builder(default)]
+  /// Chooses the maximum heap, overriding the one set in `jvm_options`
+  pub heap_strategy: Option<HeapStrategy>,
   #[builder(default)]
   /// Overrides the default arguments applied to the JVM
   pub jvm_args: Option<Vec<String>>,
   #[builder(default)]
+  /// Commands the game is launched through, outermost first (e.g. `["gamemoderun"]`, `["firejail", "--noprofile"]`)
+  pub wrapper_commands: Vec<Vec<String>>,
+  #[builder(default)]
+  /// Environment variables set for the game process
+  pub env_vars: HashMap<String, String>,
+  #[builder(default)]
+  /// Starts the game with only `env_vars` instead of inheriting the launcher's environment
+  pub clear_env: bool,
+  #[builder(default)]
+  /// Commands run before the game starts, with the launch variables substituted
+  pub pre_launch_hooks: Vec<HookCommand>,
+  #[builder(default)]
+  /// Commands run after the game exits, with the launch variables substituted
+  pub post_exit_hooks: Vec<HookCommand>,
+  #[builder(default)]
+  /// Writes JVM arguments to a temporary file removed once the game exits, ignored for Java 8
+  pub argument_file: ArgumentFileMode,
+  #[builder(default)]
+  /// Fails the launch if an argument contains an unknown `${...}` placeholder
+  pub strict_arguments: bool,
+  #[builder(default)]
   /// Custom substitution rules applied to the JVM and game argument variables
   pub substitutor_overrides: HashMap<String, String>,
 
@@ -80,6 +167,11 @@ pub struct GameOptions {
 }
 
 impl GameOptions {
+  /// Directory holding the `libraries`, `assets` and `versions`
+  pub fn shared_dir(&self) -> &Path {
+    self.shared_dir.as_deref().unwrap_or(&self.game_dir)
+  }
+
   pub fn env_features(&self) -> EnvironmentFeatures {
     let mut env_features = EnvironmentFeatures::new();
     if let Some(demo) = self.demo {
@@ -88,6 +180,12 @@ impl GameOptions {
     if self.resolution.is_some() {
       env_features.set_feature(RuleFeatureType::HasCustomResolution, json!(true));
     }
+    if self.quick_play_path.is_some() {
+      env_features.set_feature(RuleFeatureType::HasQuickPlaysSupport, json!(true));
+    }
+    if let Some(quick_play) = &self.quick_play {
+      env_features.set_feature(quick_play.feature(), json!(true));
+    }
     env_features
   }
 }
@@ -1,23 +1,45 @@
-use std::{ io::BufReader, path::{ Path, PathBuf }, process::{ Child, ChildStderr, ChildStdout, Command, Stdio } };
+use std::{ collections::HashMap, fs, io::BufReader, mem::take, path::{ Path, PathBuf }, process::{ Child, ChildStderr, ChildStdout, Command, Stdio } };
 
-use crate::json::manifest::rule::OperatingSystem;
+use log::warn;
 
-use super::error::Error;
+use super::{ argument_substitutor::UnresolvedVariable, error::Error, hooks::{ run_hooks, HookCommand }, script::quote_shell };
 
 pub struct GameProcess {
   child: Child,
   stdout: BufReader<ChildStdout>,
   stderr: BufReader<ChildStderr>,
+  post_exit_hooks: Vec<HookCommand>,
+  argument_file: Option<PathBuf>,
 }
 
 impl GameProcess {
   pub fn new(java_path: &PathBuf, game_dir: &PathBuf, args: Vec<String>) -> Self {
-    let mut child = Command::new(java_path).stdout(Stdio::piped()).stderr(Stdio::piped()).current_dir(game_dir).args(args).spawn().unwrap();
-    Self {
+    let mut command = Command::new(java_path);
+    command.current_dir(game_dir).args(args);
+    Self::spawn_command(command).unwrap()
+  }
+
+  /// Spawns the command with piped stdout and stderr
+  pub fn spawn_command(mut command: Command) -> Result<Self, std::io::Error> {
+    let mut child = command.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    Ok(Self {
       stdout: BufReader::new(child.stdout.take().unwrap()),
       stderr: BufReader::new(child.stderr.take().unwrap()),
       child,
-    }
+      post_exit_hooks: vec![],
+      argument_file: None,
+    })
+  }
+
+  pub fn with_post_exit_hooks(mut self, hooks: Vec<HookCommand>) -> Self {
+    self.post_exit_hooks = hooks;
+    self
+  }
+
+  /// Sets the argument file of the launch, removed once the game has exited
+  pub fn with_argument_file(mut self, argument_file: Option<PathBuf>) -> Self {
+    self.argument_file = argument_file;
+    self
   }
 
   pub fn inner(&self) -> &Child {
@@ -40,10 +62,47 @@ impl GameProcess {
     }
   }
 
-  pub fn into_inner(self) -> Child {
+  /// Waits for the game to exit and runs the post-exit hooks, returning the exit code of the game.
+  /// A failing hook is logged, it doesn't hide the exit code.
+  ///
+  /// The game may block if its output isn't read, so make sure `stdout` and `stderr` are drained.
+  pub fn wait(&mut self) -> Result<Option<i32>, Error> {
+    let status = self.child.wait()?;
+    if let Err(err) = self.run_post_exit_hooks() {
+      warn!("Post-exit hook failed: {err}");
+    }
+    Ok(status.code())
+  }
+
+  /// Removes the argument file and runs the post-exit hooks, if they haven't been run yet.
+  /// Call this once the game has exited when not using `GameProcess::wait`.
+  pub fn run_post_exit_hooks(&mut self) -> Result<(), Error> {
+    if let Some(argument_file) = self.argument_file.take() {
+      if let Err(err) = fs::remove_file(&argument_file) {
+        warn!("Couldn't remove argument file {}: {err}", argument_file.display());
+      }
+    }
+    run_hooks(&take(&mut self.post_exit_hooks))?;
+    Ok(())
+  }
+
+  /// Returns the child process and the post-exit hooks that haven't been run yet.
+  /// The caller is responsible for running them with `hooks::run_hooks` once the game has exited.
+  /// The argument file of the launch, if any, is left in place as the game may still read it.
+  pub fn into_parts(mut self) -> (Child, Vec<HookCommand>) {
+    let hooks = take(&mut self.post_exit_hooks);
     let mut child = self.child;
     child.stdout.replace(self.stdout.into_inner());
     child.stderr.replace(self.stderr.into_inner());
+    (child, hooks)
+  }
+
+  /// Returns the child process. The post-exit hooks are skipped, use `GameProcess::into_parts` to keep them.
+  pub fn into_inner(self) -> Child {
+    let (child, hooks) = self.into_parts();
+    if !hooks.is_empty() {
+      warn!("Skipping {} post-exit hook(s) of the game process", hooks.len());
+    }
     child
   }
 }
@@ -59,6 +118,20 @@ pub struct GameProcessBuilder {
   pub arguments: Vec<String>,
   pub java_path: Option<PathBuf>,
   pub directory: Option<PathBuf>,
+  /// Commands the game is launched through, outermost first (e.g. `gamemoderun`, `prime-run`)
+  pub wrappers: Vec<Vec<String>>,
+  /// Environment variables set for the game process
+  pub env: HashMap<String, String>,
+  /// Whether to start from an empty environment instead of inheriting the launcher's
+  pub clear_env: bool,
+  /// Run before the game is spawned
+  pub pre_launch_hooks: Vec<HookCommand>,
+  /// Run by `GameProcess` once the game has exited
+  pub post_exit_hooks: Vec<HookCommand>,
+  /// `${...}` placeholders left in the arguments when the launch was prepared
+  pub unresolved_variables: Vec<UnresolvedVariable>,
+  /// Argument file written for this launch, removed by `GameProcess` once the game has exited
+  pub argument_file: Option<PathBuf>,
 }
 
 impl GameProcessBuilder {
@@ -90,16 +163,109 @@ impl GameProcessBuilder {
     self
   }
 
-  pub fn spawn(self) -> Result<GameProcess, Error> {
+  /// Adds a command to launch the game through. Wrappers are chained in the order they're added.
+  pub fn with_wrapper(&mut self, wrapper: Vec<impl AsRef<str>>) -> &mut Self {
+    if !wrapper.is_empty() {
+      self.wrappers.push(wrapper.iter().map(|s| s.as_ref().to_string()).collect());
+    }
+    self
+  }
+
+  pub fn with_env(&mut self, key: impl AsRef<str>, value: impl AsRef<str>) -> &mut Self {
+    self.env.insert(key.as_ref().to_string(), value.as_ref().to_string());
+    self
+  }
+
+  pub fn with_envs(&mut self, envs: &HashMap<String, String>) -> &mut Self {
+    self.env.extend(envs.clone());
+    self
+  }
+
+  pub fn clear_env(&mut self, clear_env: bool) -> &mut Self {
+    self.clear_env = clear_env;
+    self
+  }
+
+  pub fn with_pre_launch_hook(&mut self, hook: HookCommand) -> &mut Self {
+    self.pre_launch_hooks.push(hook);
+    self
+  }
+
+  pub fn with_post_exit_hook(&mut self, hook: HookCommand) -> &mut Self {
+    self.post_exit_hooks.push(hook);
+    self
+  }
+
+  /// Returns the program and arguments to run, with the wrappers applied
+  pub fn get_command_line(&self) -> Result<Vec<String>, Error> {
     let java_path = self.java_path.as_ref().ok_or(Error::Game("Java path not set".into()))?;
+    let mut command_line: Vec<String> = self.wrappers.iter().flatten().cloned().collect();
+    command_line.push(java_path.to_string_lossy().to_string());
+
+    command_line.append(&mut self.get_args());
+    Ok(command_line)
+  }
+
+  /// Creates the command that launches the game
+  pub fn command(&self) -> Result<Command, Error> {
     let directory = self.directory.as_ref().ok_or(Error::Game("Game directory not set".into()))?;
-    let mut args = self.get_args();
-    if OperatingSystem::get_current_platform() == OperatingSystem::Windows {
-      args = args
-        .into_iter()
-        .map(|arg| arg.replace('"', "\\\""))
-        .collect();
+    let command_line = self.get_command_line()?;
+
+    let mut command = Command::new(&command_line[0]);
+    command.args(&command_line[1..]).current_dir(directory);
+    if self.clear_env {
+      command.env_clear();
     }
-    Ok(GameProcess::new(java_path, directory, args))
+    command.envs(&self.env);
+    Ok(command)
+  }
+
+  /// Renders the command for display purposes, e.g. `KEY=value gamemoderun /usr/bin/java ...`
+  pub fn display_command(&self) -> Result<String, Error> {
+    let mut env: Vec<_> = self.env.iter().collect();
+    env.sort();
+
+    let mut parts: Vec<String> = env
+      .into_iter()
+      .map(|(key, value)| format!("{key}={}", quote_shell(value)))
+      .collect();
+    if self.clear_env {
+      parts.insert(0, "env -i".to_string());
+    }
+    parts.extend(self.get_command_line()?.iter().map(|arg| quote_shell(arg)));
+    Ok(parts.join(" "))
+  }
+
+  /// Runs the pre-launch hooks and spawns the game
+  pub fn spawn(self) -> Result<GameProcess, Error> {
+    let command = self.command()?;
+    run_hooks(&self.pre_launch_hooks)?;
+    let process = match GameProcess::spawn_command(command) {
+      Ok(process) => process,
+      Err(err) => {
+        if let Some(argument_file) = &self.argument_file {
+          let _ = fs::remove_file(argument_file);
+        }
+        return Err(Error::Game(err.into()));
+      }
+    };
+    Ok(process.with_post_exit_hooks(self.post_exit_hooks).with_argument_file(self.argument_file))
+  }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_wait_with_failing_hook() {
+    let mut command = Command::new("sh");
+    command.args(["-c", "exit 3"]);
+    let hook = HookCommand::new(vec!["false"]).abort_on_failure(true);
+    let argument_file = std::env::temp_dir().join(format!("mlc-wait-arguments-{}.txt", std::process::id()));
+    fs::write(&argument_file, "-Xmx2G").unwrap();
+    let mut process = GameProcess::spawn_command(command).unwrap().with_post_exit_hooks(vec![hook]).with_argument_file(Some(argument_file.clone()));
+    assert_eq!(process.wait().unwrap(), Some(3));
+    assert!(!argument_file.exists());
   }
 }
@@ -0,0 +1,260 @@
+use std::{ fs, path::Path };
+
+use log::warn;
+
+use super::{ error::Error, hooks::HookCommand, process::GameProcessBuilder };
+
+/// Text written in place of redacted values
+pub const REDACTED: &str = "?????";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptFormat {
+  /// POSIX `sh` script
+  Shell,
+  /// Windows `.bat` script
+  Batch,
+}
+
+/// Turns a prepared `GameProcessBuilder` into a script that launches the game without the launcher
+pub struct LaunchScript<'a> {
+  builder: &'a GameProcessBuilder,
+  redactions: Vec<String>,
+}
+
+impl<'a> LaunchScript<'a> {
+  pub fn new(builder: &'a GameProcessBuilder) -> Self {
+    Self { builder, redactions: vec![] }
+  }
+
+  /// Replaces every occurrence of `secret` (e.g. the access token) with `REDACTED`
+  pub fn redact(mut self, secret: impl AsRef<str>) -> Self {
+    if !secret.as_ref().is_empty() {
+      self.redactions.push(secret.as_ref().to_string());
+    }
+    self
+  }
+
+  pub fn render(&self, format: ScriptFormat) -> Result<String, Error> {
+    match format {
+      ScriptFormat::Shell => self.render_shell(),
+      ScriptFormat::Batch => self.render_batch(),
+    }
+  }
+
+  /// Writes the script to `path`, making it executable on unix
+  pub fn write(&self, format: ScriptFormat, path: &Path) -> Result<(), Error> {
+    fs::write(path, self.render(format)?)?;
+    #[cfg(unix)]
+    if format == ScriptFormat::Shell {
+      use std::os::unix::fs::PermissionsExt;
+      fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(())
+  }
+
+  fn redacted(&self, value: &str) -> String {
+    self.redactions.iter().fold(value.to_string(), |value, secret| value.replace(secret, REDACTED))
+  }
+
+  fn command_line(&self) -> Result<Vec<String>, Error> {
+    let GameProcessBuilder { wrappers, java_path, arguments, .. } = self.builder;
+    let java_path = java_path.as_ref().ok_or(Error::Game("Java path not set".into()))?;
+
+    let mut command_line: Vec<String> = wrappers.iter().flatten().cloned().collect();
+    command_line.push(java_path.to_string_lossy().to_string());
+    command_line.extend(arguments.iter().cloned());
+    Ok(
+      command_line
+        .iter()
+        .map(|arg| self.redacted(arg))
+        .collect()
+    )
+  }
+
+  fn sorted_env(&self) -> Vec<(String, String)> {
+    let mut env: Vec<_> = self.builder.env
+      .iter()
+      .map(|(key, value)| (key.clone(), self.redacted(value)))
+      .collect();
+    env.sort();
+    env
+  }
+
+  fn hook_line(&self, hook: &HookCommand, quote: fn(&str) -> String) -> String {
+    hook.command
+      .iter()
+      .map(|arg| quote(&self.redacted(arg)))
+      .collect::<Vec<_>>()
+      .join(" ")
+  }
+
+  fn render_shell(&self) -> Result<String, Error> {
+    let builder = self.builder;
+    let mut lines = vec!["#!/bin/sh".to_string(), "# Generated by minecraft-launcher-core".to_string()];
+
+    if let Some(directory) = &builder.directory {
+      lines.push(format!("cd {} || exit 1", quote_shell(&directory.to_string_lossy())));
+    }
+
+    let env = self.sorted_env();
+    if !builder.clear_env {
+      lines.extend(env.iter().map(|(key, value)| format!("export {key}={}", quote_shell(value))));
+    }
+
+    for hook in &builder.pre_launch_hooks {
+      let line = self.hook_line(hook, quote_shell);
+      lines.push(if hook.abort_on_failure { format!("{line} || exit 1") } else { line });
+    }
+
+    let mut command: Vec<String> = vec![];
+    if builder.clear_env {
+      command.push("env -i".to_string());
+      command.extend(env.iter().map(|(key, value)| quote_shell(&format!("{key}={value}"))));
+    }
+    command.extend(self.command_line()?.iter().map(|arg| quote_shell(arg)));
+    let command = command.join(" ");
+
+    if builder.post_exit_hooks.is_empty() {
+      lines.push(format!("exec {command}"));
+    } else {
+      lines.push(command);
+      lines.push("status=$?".to_string());
+      lines.extend(builder.post_exit_hooks.iter().map(|hook| self.hook_line(hook, quote_shell)));
+      lines.push("exit $status".to_string());
+    }
+
+    lines.push(String::new());
+    Ok(lines.join("\n"))
+  }
+
+  fn render_batch(&self) -> Result<String, Error> {
+    let builder = self.builder;
+    let mut lines = vec!["@echo off".to_string(), "rem Generated by minecraft-launcher-core".to_string(), "setlocal".to_string()];
+
+    if let Some(directory) = &builder.directory {
+      lines.push(format!("cd /d {} || exit /b 1", quote_batch(&directory.to_string_lossy())));
+    }
+
+    if builder.clear_env {
+      warn!("Batch scripts can't clear the environment, the launcher's environment will be inherited");
+      lines.push("rem The game was configured to start with a cleared environment".to_string());
+    }
+    lines.extend(
+      self
+        .sorted_env()
+        .iter()
+        .map(|(key, value)| format!("set \"{key}={}\"", value.replace('%', "%%")))
+    );
+
+    for hook in &builder.pre_launch_hooks {
+      let line = self.hook_line(hook, quote_batch);
+      lines.push(if hook.abort_on_failure { format!("{line} || exit /b 1") } else { line });
+    }
+
+    let command = self
+      .command_line()?
+      .iter()
+      .map(|arg| quote_batch(arg))
+      .collect::<Vec<_>>()
+      .join(" ");
+    lines.push(command);
+
+    if !builder.post_exit_hooks.is_empty() {
+      lines.push("set status=%ERRORLEVEL%".to_string());
+      lines.extend(builder.post_exit_hooks.iter().map(|hook| self.hook_line(hook, quote_batch)));
+      lines.push("exit /b %status%".to_string());
+    }
+
+    lines.push(String::new());
+    Ok(lines.join("\r\n"))
+  }
+}
+
+/// Quotes an argument for POSIX shells, leaving simple arguments untouched
+pub fn quote_shell(arg: &str) -> String {
+  let is_safe = |c: char| c.is_ascii_alphanumeric() || "_@%+=:,./-".contains(c);
+  if !arg.is_empty() && arg.chars().all(is_safe) {
+    arg.to_string()
+  } else {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+  }
+}
+
+/// Quotes an argument for a Java `@argfile`, where backslashes inside quotes are escapes
+pub fn quote_argument_file(arg: &str) -> String {
+  format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Quotes an argument for `cmd.exe`, following the MSVC command line parsing rules
+pub fn quote_batch(arg: &str) -> String {
+  let needs_quotes = arg.is_empty() || arg.contains(|c: char| c.is_whitespace() || "\"&|<>^(),;=%!".contains(c));
+  let arg = arg.replace('%', "%%");
+  if !needs_quotes {
+    return arg;
+  }
+
+  let mut quoted = String::from('"');
+  let mut backslashes = 0;
+  for c in arg.chars() {
+    match c {
+      '\\' => {
+        backslashes += 1;
+      }
+      '"' => {
+        // Backslashes before a quote are escapes, double them and escape the quote
+        quoted.push_str(&"\\".repeat(backslashes * 2 + 1));
+        quoted.push('"');
+        backslashes = 0;
+      }
+      c => {
+        quoted.push_str(&"\\".repeat(backslashes));
+        quoted.push(c);
+        backslashes = 0;
+      }
+    }
+  }
+  // Backslashes before the closing quote must be doubled too
+  quoted.push_str(&"\\".repeat(backslashes * 2));
+  quoted.push('"');
+  quoted
+}
+
+#[cfg(test)]
+mod tests {
+  use std::path::Path;
+
+  use super::*;
+
+  #[test]
+  fn test_launch_script() {
+    let mut builder = GameProcessBuilder::new();
+    builder
+      .with_java_path(Path::new("/opt/java/bin/java"))
+      .directory(Path::new("/home/player/.minecraft"))
+      .with_wrapper(vec!["gamemoderun"])
+      .with_env("__GL_THREADED_OPTIMIZATIONS", "1")
+      .with_arguments(vec!["-Dname=It's mine", "--accessToken", "secret-token"]);
+
+    let script = LaunchScript::new(&builder).redact("secret-token");
+    let shell = script.render(ScriptFormat::Shell).unwrap();
+    assert!(shell.contains("export __GL_THREADED_OPTIMIZATIONS=1\n"));
+    assert!(shell.contains("exec gamemoderun /opt/java/bin/java '-Dname=It'\\''s mine' --accessToken '?????'\n"));
+
+    let batch = script.render(ScriptFormat::Batch).unwrap();
+    assert!(batch.contains("set \"__GL_THREADED_OPTIMIZATIONS=1\"\r\n"));
+    assert!(batch.contains("gamemoderun /opt/java/bin/java \"-Dname=It's mine\" --accessToken ?????\r\n"));
+  }
+
+  #[test]
+  fn test_quote_batch() {
+    assert_eq!(quote_batch("C:\\Program Files\\"), "\"C:\\Program Files\\\\\"");
+    assert_eq!(quote_batch("say \"hi\""), "\"say \\\"hi\\\"\"");
+    assert_eq!(quote_batch("100%"), "\"100%%\"");
+  }
+
+  #[test]
+  fn test_quote_argument_file() {
+    assert_eq!(quote_argument_file("C:\\Program Files\\lib.jar"), "\"C:\\\\Program Files\\\\lib.jar\"");
+    assert_eq!(quote_argument_file("-Dname=\"x\""), "\"-Dname=\\\"x\\\"\"");
+  }
+}
@@ -1,14 +1,14 @@
-use base64::{engine::general_purpose, Engine as _};
 use serde::Deserialize;
-use uuid::Uuid;
 
 #[derive(Debug, Deserialize)]
 pub(crate) struct JwtPayload {
+    #[allow(dead_code)]
     pub(crate) profiles: Profiles,
     pub(crate) pfd: Vec<Pfd>,
 }
 
 #[derive(Debug, Deserialize)]
+#[allow(dead_code)]
 pub(crate) struct Profiles {
     pub(crate) mc: String,
 }
@@ -0,0 +1,466 @@
+use std::{ collections::HashMap, error::Error, fs::File, io::Read, path::Path };
+
+use async_trait::async_trait;
+use futures::{ stream::iter, StreamExt };
+use log::{ info, warn };
+use reqwest::Client;
+use serde::{ de::DeserializeOwned, Deserialize, Serialize };
+use serde_json::json;
+use zip::ZipArchive;
+
+use crate::{
+  json::{ manifest::VersionManifest, MCVersion, Sha1Sum },
+  mod_loaders::neoforge::NeoForgeInstaller,
+  version_manager::{
+    downloader::{ download_job::DownloadJob, downloadables::{ Downloadable, EtagDownloadable, PreHashedDownloadable }, progress::ProgressReporter },
+    VersionManager,
+  },
+};
+
+use super::{ enclosed_path, error::InstanceError, extract_archive_dir, install_mod_loader, Instance, InstanceManager, ModLoader, ModLoaderKind };
+
+pub const CURSEFORGE_MANIFEST: &str = "manifest.json";
+pub const CURSEFORGE_API_URL: &str = "https://api.curseforge.com";
+/// Files resolved at the same time by the default `CurseForgeResolver::resolve_all`
+const CONCURRENT_RESOLVES: usize = 8;
+
+/// The `manifest.json` of a CurseForge modpack zip
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeManifest {
+  pub minecraft: CurseForgeMinecraft,
+  /// `minecraftModpack`
+  pub manifest_type: String,
+  pub manifest_version: u32,
+  pub name: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub version: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub author: Option<String>,
+  #[serde(default)]
+  pub files: Vec<CurseForgeFile>,
+  /// Directory of the zip copied into the game directory
+  #[serde(default = "default_overrides")]
+  pub overrides: String,
+}
+
+fn default_overrides() -> String {
+  "overrides".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeMinecraft {
+  pub version: MCVersion,
+  #[serde(default)]
+  pub mod_loaders: Vec<CurseForgeModLoader>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurseForgeModLoader {
+  /// The loader and its version, e.g. `forge-47.2.0` or `fabric-0.15.11`
+  pub id: String,
+  #[serde(default)]
+  pub primary: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CurseForgeFile {
+  #[serde(rename = "projectID")]
+  pub project_id: u64,
+  #[serde(rename = "fileID")]
+  pub file_id: u64,
+  /// Optional files the user disabled are not required, they aren't installed and are listed in `CurseForgeImport::skipped`
+  #[serde(default = "default_required")]
+  pub required: bool,
+}
+
+fn default_required() -> bool {
+  true
+}
+
+impl CurseForgeManifest {
+  /// The primary loader, or the first one if none is marked as primary
+  pub fn mod_loader(&self) -> Option<ModLoader> {
+    let loaders = &self.minecraft.mod_loaders;
+    let loader = loaders.iter().find(|loader| loader.primary).or(loaders.first())?;
+    let (kind, version) = loader.id.split_once('-')?;
+    let kind = match kind {
+      "forge" => ModLoaderKind::Forge,
+      "neoforge" => ModLoaderKind::NeoForge,
+      "fabric" => ModLoaderKind::Fabric,
+      "quilt" => ModLoaderKind::Quilt,
+      _ => return None,
+    };
+    let version = match kind {
+      ModLoaderKind::NeoForge => NeoForgeInstaller::normalize_version(&self.minecraft.version, version),
+      _ => version.to_string(),
+    };
+    Some(ModLoader { kind, version })
+  }
+}
+
+/// Where to download a modpack file from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedFile {
+  pub url: String,
+  /// Destination relative to the game directory, e.g. `mods/jei-1.20.1-forge-15.3.0.4.jar`
+  pub path: String,
+  /// Checked after downloading if set, otherwise the ETag of the response is used
+  pub sha1: Option<Sha1Sum>,
+}
+
+pub type ResolveResult = Result<Option<ResolvedFile>, Box<dyn Error + Send + Sync>>;
+
+/// Looks up the download of the files of a CurseForge modpack
+#[async_trait]
+pub trait CurseForgeResolver: Send + Sync {
+  /// Returns `None` if the file can't be downloaded, e.g. its author disabled third-party downloads
+  async fn resolve(&self, file: &CurseForgeFile) -> ResolveResult;
+
+  /// Resolves several files, in the same order. Runs `resolve` concurrently by default
+  async fn resolve_all(&self, files: &[CurseForgeFile]) -> Vec<ResolveResult> {
+    let resolves: Vec<_> = files.iter().map(|file| self.resolve(file)).collect();
+    iter(resolves)
+      .buffered(CONCURRENT_RESOLVES)
+      .collect()
+      .await
+  }
+}
+
+/// Resolves files with the CurseForge API, which requires an API key
+#[derive(Debug, Clone)]
+pub struct CurseForgeApiResolver {
+  pub api_url: String,
+  pub api_key: String,
+  pub client: Client,
+}
+
+#[derive(Deserialize)]
+struct ApiResponse<T> {
+  data: T,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiFile {
+  id: u64,
+  mod_id: u64,
+  file_name: String,
+  download_url: Option<String>,
+  #[serde(default)]
+  hashes: Vec<ApiHash>,
+}
+
+#[derive(Deserialize)]
+struct ApiHash {
+  value: String,
+  /// 1 for SHA-1, 2 for MD5
+  algo: u8,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiMod {
+  id: u64,
+  class_id: Option<u32>,
+}
+
+impl CurseForgeApiResolver {
+  pub fn new(client: &Client, api_key: &str) -> Self {
+    Self::with_api_url(client, api_key, CURSEFORGE_API_URL)
+  }
+
+  pub fn with_api_url(client: &Client, api_key: &str, api_url: &str) -> Self {
+    Self { api_url: api_url.trim_end_matches('/').to_string(), api_key: api_key.to_string(), client: client.clone() }
+  }
+
+  async fn post<T: DeserializeOwned>(&self, path: &str, body: &serde_json::Value) -> Result<T, reqwest::Error> {
+    let response = self.client.post(format!("{}{path}", self.api_url)).header("x-api-key", &self.api_key).json(body).send().await?;
+    Ok(response.error_for_status()?.json::<ApiResponse<T>>().await?.data)
+  }
+
+  /// Looks up the files and their projects with the batch endpoints, two requests for the whole modpack
+  async fn fetch_all(&self, files: &[CurseForgeFile]) -> Result<(Vec<ApiFile>, Vec<ApiMod>), reqwest::Error> {
+    let file_ids: Vec<u64> = files.iter().map(|file| file.file_id).collect();
+    let api_files: Vec<ApiFile> = self.post("/v1/mods/files", &json!({ "fileIds": file_ids })).await?;
+    let mut mod_ids: Vec<u64> = api_files.iter().map(|file| file.mod_id).collect();
+    mod_ids.sort_unstable();
+    mod_ids.dedup();
+    let mods: Vec<ApiMod> = self.post("/v1/mods", &json!({ "modIds": mod_ids })).await?;
+    Ok((api_files, mods))
+  }
+}
+
+/// Matches the API files and projects with the modpack files
+fn resolve_api_files(files: &[CurseForgeFile], api_files: Vec<ApiFile>, mods: Vec<ApiMod>) -> Vec<ResolveResult> {
+  let class_ids: HashMap<u64, Option<u32>> = mods.into_iter().map(|project| (project.id, project.class_id)).collect();
+  let mut api_files: HashMap<u64, ApiFile> = api_files.into_iter().map(|file| (file.id, file)).collect();
+  files
+    .iter()
+    .map(|file| {
+      let api_file = api_files
+        .remove(&file.file_id)
+        .filter(|api_file| api_file.mod_id == file.project_id)
+        .ok_or_else(|| format!("file {} of project {} not found", file.file_id, file.project_id))?;
+      let Some(url) = api_file.download_url else {
+        return Ok(None);
+      };
+      let class_id = class_ids.get(&file.project_id).ok_or_else(|| format!("project {} not found", file.project_id))?;
+      let dir = match class_id {
+        Some(6) => "mods",
+        Some(12) => "resourcepacks",
+        Some(6552) => "shaderpacks",
+        // Worlds (17) and other classes aren't installed in a known directory
+        Some(class_id) => return Err(format!("unsupported project class {class_id}").into()),
+        None => return Err(format!("unknown class of project {}", file.project_id).into()),
+      };
+      let sha1 = api_file.hashes
+        .into_iter()
+        .find(|hash| hash.algo == 1)
+        .and_then(|hash| Sha1Sum::try_from(hash.value).ok());
+      Ok(Some(ResolvedFile { url, path: format!("{dir}/{}", api_file.file_name), sha1 }))
+    })
+    .collect()
+}
+
+#[async_trait]
+impl CurseForgeResolver for CurseForgeApiResolver {
+  async fn resolve(&self, file: &CurseForgeFile) -> ResolveResult {
+    self.resolve_all(std::slice::from_ref(file)).await.remove(0)
+  }
+
+  async fn resolve_all(&self, files: &[CurseForgeFile]) -> Vec<ResolveResult> {
+    if files.is_empty() {
+      return vec![];
+    }
+    match self.fetch_all(files).await {
+      Ok((api_files, mods)) => resolve_api_files(files, api_files, mods),
+      Err(err) => files.iter().map(|_| Err(err.to_string().into())).collect(),
+    }
+  }
+}
+
+/// A file of the modpack that wasn't installed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnresolvedFile {
+  pub file: CurseForgeFile,
+  pub reason: String,
+}
+
+/// The result of importing a CurseForge modpack
+#[derive(Debug, Clone)]
+pub struct CurseForgeImport {
+  pub instance: Instance,
+  /// Manifest of the installed loader, `None` for vanilla modpacks or when the loader must be installed
+  pub manifest: Option<VersionManifest>,
+  /// Files the resolver couldn't find, the user must download them by hand
+  pub unresolved: Vec<UnresolvedFile>,
+  /// Optional files that weren't required, they aren't installed
+  pub skipped: Vec<CurseForgeFile>,
+}
+
+pub fn read_manifest(archive: &mut ZipArchive<File>) -> Result<CurseForgeManifest, InstanceError> {
+  let mut manifest = String::new();
+  archive
+    .by_name(CURSEFORGE_MANIFEST)
+    .map_err(|_| InstanceError::InvalidModpack(format!("missing {CURSEFORGE_MANIFEST}")))?
+    .read_to_string(&mut manifest)?;
+  let manifest: CurseForgeManifest = serde_json::from_str(&manifest)?;
+  if manifest.manifest_type != "minecraftModpack" {
+    return Err(InstanceError::InvalidModpack(format!("unsupported manifest type {}", manifest.manifest_type)));
+  }
+  Ok(manifest)
+}
+
+/// Creates an instance from a CurseForge modpack zip.
+///
+/// The required files are looked up with `resolver` and downloaded, the ones it can't resolve are reported instead of
+/// failing the import. Files that aren't required are skipped and listed in the result. Forge and NeoForge are only
+/// installed if `java_path` is set, as their installer must run. The instance is removed if the import fails once created.
+///
+/// # Errors
+/// Returns an `InstanceError` if the modpack is invalid, a resolved file can't be downloaded or the loader can't be installed.
+pub async fn import(
+  path: &Path,
+  instances: &InstanceManager,
+  version_manager: &mut VersionManager,
+  resolver: &dyn CurseForgeResolver,
+  java_path: Option<&Path>,
+  reporter: &ProgressReporter
+) -> Result<CurseForgeImport, InstanceError> {
+  let mut archive = ZipArchive::new(File::open(path)?)?;
+  let manifest = read_manifest(&mut archive)?;
+  let minecraft_version = manifest.minecraft.version.clone();
+  info!("Importing {} for {}", manifest.name, minecraft_version);
+
+  let (required, skipped): (Vec<CurseForgeFile>, Vec<CurseForgeFile>) = manifest.files.iter().partition(|file| file.required);
+  let mut resolved = vec![];
+  let mut unresolved = vec![];
+  for (file, result) in required.iter().zip(resolver.resolve_all(&required).await) {
+    let reason = match result {
+      Ok(Some(resolved_file)) if enclosed_path(Path::new(""), &resolved_file.path).is_some() => {
+        resolved.push(resolved_file);
+        continue;
+      }
+      Ok(Some(resolved_file)) => format!("unsafe file path {}", resolved_file.path),
+      Ok(None) => "not available for download".to_string(),
+      Err(err) => err.to_string(),
+    };
+    warn!("Couldn't resolve file {} of project {}: {reason}", file.file_id, file.project_id);
+    unresolved.push(UnresolvedFile { file: *file, reason });
+  }
+
+  let mut instance = instances.create(&manifest.name, minecraft_version.clone())?;
+  instance.mod_loader = manifest.mod_loader();
+  let game_dir = instances.game_dir(&instance.id);
+
+  let downloadables = resolved
+    .into_iter()
+    .map(|file| -> Box<dyn Downloadable + Send + Sync> {
+      let target = game_dir.join(&file.path);
+      match file.sha1 {
+        Some(sha1) => Box::new(PreHashedDownloadable::new(&file.url, &target, sha1)),
+        None => Box::new(EtagDownloadable::new(&file.url, &target, false)),
+      }
+    })
+    .collect();
+  let installed = async {
+    DownloadJob::new("Modpack files")
+      .with_client(version_manager.client.clone())
+      .with_progress_reporter(reporter)
+      .add_downloadables(downloadables)
+      .start().await?;
+    extract_archive_dir(&mut archive, &format!("{}/", manifest.overrides.trim_end_matches('/')), &game_dir)?;
+
+    let manifest = install_mod_loader(version_manager, &mut instance, java_path, reporter).await?;
+    instances.save(&instance)?;
+    Ok(manifest)
+  }.await;
+
+  match installed {
+    Ok(manifest) => Ok(CurseForgeImport { instance, manifest, unresolved, skipped }),
+    Err(err) => Err(instances.discard(&instance, err)),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{ collections::HashMap, env::temp_dir, fs, io::Write, sync::Arc };
+
+  use zip::{ write::SimpleFileOptions, ZipWriter };
+
+  use crate::{ json::EnvironmentFeatures, tests::serve_files, version_manager::downloader::progress::EmptyReporter };
+
+  use super::*;
+
+  /// Stands in for the API, no file can be downloaded
+  struct OfflineResolver;
+
+  #[async_trait]
+  impl CurseForgeResolver for OfflineResolver {
+    async fn resolve(&self, file: &CurseForgeFile) -> ResolveResult {
+      match file.project_id {
+        238222 => Ok(None),
+        _ => Err(format!("project {} not found", file.project_id).into()),
+      }
+    }
+  }
+
+  /// Resolves every file to a URL that doesn't exist
+  struct BrokenResolver(String);
+
+  #[async_trait]
+  impl CurseForgeResolver for BrokenResolver {
+    async fn resolve(&self, file: &CurseForgeFile) -> ResolveResult {
+      let path = format!("mods/{}.jar", file.file_id);
+      Ok(Some(ResolvedFile { url: format!("{}/{path}", self.0), path, sha1: None }))
+    }
+  }
+
+  #[tokio::test]
+  async fn test_import_curseforge() {
+    let root = temp_dir().join(format!("mlc-curseforge-{}", std::process::id()));
+    fs::create_dir_all(&root).unwrap();
+    let path = root.join("pack.zip");
+
+    let manifest = serde_json::json!({
+      "minecraft": { "version": "1.20.1", "modLoaders": [] },
+      "manifestType": "minecraftModpack",
+      "manifestVersion": 1,
+      "name": "Vanilla Plus",
+      "version": "1.0.0",
+      "author": "someone",
+      "files": [
+        { "projectID": 238222, "fileID": 4712868, "required": true },
+        { "projectID": 306612, "fileID": 4596739, "required": true },
+        { "projectID": 32274, "fileID": 4586426, "required": false }
+      ],
+      "overrides": "overrides"
+    });
+    let mut zip = ZipWriter::new(File::create(&path).unwrap());
+    for (name, contents) in [(CURSEFORGE_MANIFEST, manifest.to_string()), ("overrides/config/jei.toml", "a = 1".to_string())] {
+      zip.start_file(name, SimpleFileOptions::default()).unwrap();
+      zip.write_all(contents.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+
+    let instances = InstanceManager::new(&root.join("instances"));
+    let mut version_manager = VersionManager::new(&root.join("shared"), &EnvironmentFeatures::default(), None);
+    let reporter: ProgressReporter = Arc::new(EmptyReporter);
+    let import = import(&path, &instances, &mut version_manager, &OfflineResolver, None, &reporter).await.unwrap();
+
+    assert_eq!(import.instance.name, "Vanilla Plus");
+    assert!(import.manifest.is_none());
+    assert_eq!(import.unresolved.len(), 2);
+    assert_eq!(import.unresolved[0].reason, "not available for download");
+    assert_eq!(import.unresolved[1].reason, "project 306612 not found");
+    assert_eq!(import.skipped.iter().map(|file| file.project_id).collect::<Vec<_>>(), vec![32274]);
+    assert!(instances.game_dir(&import.instance.id).join("config").join("jei.toml").is_file());
+
+    let mut manifest: CurseForgeManifest = serde_json::from_value(manifest).unwrap();
+    manifest.minecraft.mod_loaders = vec![
+      CurseForgeModLoader { id: "fabric-0.15.11".to_string(), primary: false },
+      CurseForgeModLoader { id: "neoforge-47.1.106".to_string(), primary: true },
+    ];
+    assert_eq!(manifest.mod_loader(), Some(ModLoader { kind: ModLoaderKind::NeoForge, version: "1.20.1-47.1.106".to_string() }));
+
+    // A file that can't be downloaded fails the import and removes the instance
+    let resolver = BrokenResolver(serve_files(HashMap::new()));
+    let err = super::import(&path, &instances, &mut version_manager, &resolver, None, &reporter).await.unwrap_err();
+    assert!(matches!(err, InstanceError::Download(_)));
+    assert_eq!(fs::read_dir(root.join("instances")).unwrap().count(), 1);
+
+    let _ = fs::remove_dir_all(root);
+  }
+
+  #[test]
+  fn test_resolve_api_files() {
+    let file = |project_id, file_id| CurseForgeFile { project_id, file_id, required: true };
+    let files = [file(238222, 1), file(32274, 2), file(1000, 3), file(2000, 4), file(3000, 5)];
+    let api_files: Vec<ApiFile> = serde_json::from_value(serde_json::json!([
+      {
+        "id": 1, "modId": 238222, "fileName": "jei.jar", "downloadUrl": "https://edge.forgecdn.net/jei.jar",
+        "hashes": [{ "value": "0123456789abcdef0123456789abcdef01234567", "algo": 1 }, { "value": "0123", "algo": 2 }]
+      },
+      { "id": 2, "modId": 32274, "fileName": "faithful.zip", "downloadUrl": "https://edge.forgecdn.net/faithful.zip" },
+      { "id": 3, "modId": 1000, "fileName": "world.zip", "downloadUrl": "https://edge.forgecdn.net/world.zip" },
+      { "id": 4, "modId": 2000, "fileName": "private.jar", "downloadUrl": null }
+    ])).unwrap();
+    let mods: Vec<ApiMod> = serde_json::from_value(serde_json::json!([
+      { "id": 238222, "classId": 6 },
+      { "id": 32274, "classId": 12 },
+      { "id": 1000, "classId": 17 },
+      { "id": 2000, "classId": 6 }
+    ])).unwrap();
+
+    let results = resolve_api_files(&files, api_files, mods);
+    let jei = results[0].as_ref().unwrap().as_ref().unwrap();
+    assert_eq!(jei.path, "mods/jei.jar");
+    assert_eq!(jei.sha1.as_ref().unwrap().to_string(), "0123456789abcdef0123456789abcdef01234567");
+    assert_eq!(results[1].as_ref().unwrap().as_ref().unwrap().path, "resourcepacks/faithful.zip");
+    assert_eq!(results[2].as_ref().unwrap_err().to_string(), "unsupported project class 17");
+    assert!(results[3].as_ref().unwrap().is_none());
+    assert_eq!(results[4].as_ref().unwrap_err().to_string(), "file 5 of project 3000 not found");
+  }
+}
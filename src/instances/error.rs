@@ -0,0 +1,21 @@
+use thiserror::Error;
+
+use crate::{ mod_loaders::error::ModLoaderError, version_manager::{ downloader, error::{ InstallVersionError, ResolveManifestError } } };
+
+#[derive(Debug, Error)]
+pub enum InstanceError {
+  #[error("Instance not found: {0}")] NotFound(String),
+  #[error("Invalid instance id: {0}")] InvalidId(String),
+  #[error("Instance {0} has no Java path and its version doesn't require a Java runtime")] NoJava(String),
+  #[error("Invalid game options: {0}")] Options(String),
+  #[error("Invalid Prism Launcher instance: {0}")] InvalidPrismInstance(String),
+  #[error("Invalid modpack: {0}")] InvalidModpack(String),
+  #[error("{0} doesn't match its SHA-512")] ChecksumMismatch(std::path::PathBuf),
+  #[error("Failed to download modpack files: {0}")] Download(#[from] downloader::error::Error),
+  #[error("Failed to read modpack: {0}")] Zip(#[from] zip::result::ZipError),
+  #[error(transparent)] ModLoader(#[from] ModLoaderError),
+  #[error(transparent)] InstallVersion(#[from] InstallVersionError),
+  #[error(transparent)] ResolveVersion(#[from] ResolveManifestError),
+  #[error(transparent)] IO(#[from] std::io::Error),
+  #[error(transparent)] Json(#[from] serde_json::Error),
+}
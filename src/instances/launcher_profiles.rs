@@ -0,0 +1,238 @@
+use std::{ collections::HashMap, fs::{ self, File }, path::{ Path, PathBuf } };
+
+use serde::{ Deserialize, Serialize };
+use serde_json::Value;
+
+use crate::{
+  bootstrap::{ auth::UserAuthentication, options::{ GameOptions, GameOptionsBuilder, GameOptionsBuilderError } },
+  json::{ Date, MCVersion, ReleaseType },
+  version_manager::remote::RawVersionList,
+};
+
+use super::error::InstanceError;
+
+/// File name of the vanilla launcher profiles, inside the `.minecraft` directory
+pub const LAUNCHER_PROFILES_FILE: &str = "launcher_profiles.json";
+
+/// `lastVersionId` values that follow the latest version of a type
+const LATEST_RELEASE: &str = "latest-release";
+const LATEST_SNAPSHOT: &str = "latest-snapshot";
+
+/// The `launcher_profiles.json` file of the vanilla launcher
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LauncherProfiles {
+  #[serde(default)]
+  pub profiles: HashMap<String, LauncherProfile>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub settings: Option<LauncherSettings>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub version: Option<u32>,
+
+  /// Fields written by the vanilla launcher or other tools, preserved when saving
+  #[serde(flatten)]
+  pub extra: HashMap<String, Value>,
+}
+
+impl LauncherProfiles {
+  /// Loads the profiles, a missing file gives no profiles
+  pub fn load(path: &Path) -> Result<Self, InstanceError> {
+    if !path.is_file() {
+      return Ok(Self::default());
+    }
+    Ok(serde_json::from_reader(File::open(path)?)?)
+  }
+
+  pub fn save(&self, path: &Path) -> Result<(), InstanceError> {
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_vec_pretty(self)?)?;
+    Ok(())
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProfileType {
+  LatestRelease,
+  LatestSnapshot,
+  Custom,
+  #[serde(untagged)]
+  Other(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfileResolution {
+  pub width: u32,
+  pub height: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LauncherProfile {
+  #[serde(default)]
+  pub name: String,
+  #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+  pub profile_type: Option<ProfileType>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub created: Option<Date>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub last_used: Option<Date>,
+  /// Name of a built-in icon (e.g. `Grass`) or a `data:image/png;base64,` URL
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub icon: Option<String>,
+  /// Version id, or `latest-release`/`latest-snapshot`
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub last_version_id: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub game_dir: Option<PathBuf>,
+  /// Path to the Java executable
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub java_dir: Option<PathBuf>,
+  /// JVM arguments replacing the default ones, separated by spaces
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub java_args: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub resolution: Option<ProfileResolution>,
+
+  #[serde(flatten)]
+  pub extra: HashMap<String, Value>,
+}
+
+impl LauncherProfile {
+  /// Resolves the version of the profile, following `latest-release`/`latest-snapshot` with the remote version list
+  pub fn resolve_version_id(&self, version_list: &RawVersionList) -> Option<MCVersion> {
+    let latest = match (self.last_version_id.as_deref(), &self.profile_type) {
+      (Some(LATEST_RELEASE), _) | (None, Some(ProfileType::LatestRelease)) => ReleaseType::Release,
+      (Some(LATEST_SNAPSHOT), _) | (None, Some(ProfileType::LatestSnapshot)) => ReleaseType::Snapshot,
+      (Some(version_id), _) => {
+        return Some(MCVersion::new(version_id));
+      }
+      (None, _) => {
+        return None;
+      }
+    };
+    version_list.get_latest(&latest).cloned()
+  }
+
+  pub fn java_args(&self) -> Option<Vec<String>> {
+    self.java_args.as_ref().map(|args| args.split_whitespace().map(str::to_string).collect())
+  }
+
+  /// Creates the options to launch the profile.
+  ///
+  /// `minecraft_dir` is the directory holding `launcher_profiles.json`, libraries, assets and versions. The
+  /// profile runs in its `gameDir` (or `minecraft_dir`), with `default_java_path` if it has no `javaDir`.
+  pub fn game_options_builder(&self, minecraft_dir: &Path, default_java_path: &Path, authentication: UserAuthentication) -> GameOptionsBuilder {
+    let mut builder = GameOptionsBuilder::default()
+      .java_path(self.java_dir.clone().unwrap_or(default_java_path.to_path_buf()))
+      .game_dir(self.game_dir.clone().unwrap_or(minecraft_dir.to_path_buf()))
+      .natives_dir(minecraft_dir.join("bin"))
+      .shared_dir(minecraft_dir.to_path_buf())
+      .authentication(authentication);
+    if let Some(ProfileResolution { width, height }) = self.resolution {
+      builder = builder.resolution((width, height));
+    }
+    if let Some(java_args) = self.java_args() {
+      builder = builder.jvm_args(java_args);
+    }
+    builder
+  }
+
+  pub fn to_game_options(
+    &self,
+    minecraft_dir: &Path,
+    default_java_path: &Path,
+    authentication: UserAuthentication
+  ) -> Result<GameOptions, GameOptionsBuilderError> {
+    self.game_options_builder(minecraft_dir, default_java_path, authentication).build()
+  }
+}
+
+/// The `settings` block of `launcher_profiles.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LauncherSettings {
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub crash_assistance: Option<bool>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub enable_advanced: Option<bool>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub enable_analytics: Option<bool>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub enable_historical: Option<bool>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub enable_releases: Option<bool>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub enable_snapshots: Option<bool>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub keep_launcher_open: Option<bool>,
+  /// `ByLastPlayed` or `ByName`
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub profile_sorting: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub show_game_log: Option<bool>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub show_menu: Option<bool>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub sound_on: Option<bool>,
+
+  #[serde(flatten)]
+  pub extra: HashMap<String, Value>,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_launcher_profiles() {
+    let json = serde_json::json!({
+      "profiles": {
+        "b2f1c0": {
+          "created": "2024-01-01T10:00:00.000Z",
+          "icon": "Grass",
+          "lastUsed": "2024-06-13T08:24:03.123Z",
+          "lastVersionId": "latest-release",
+          "name": "",
+          "type": "latest-release",
+          "skipJreVersionCheck": true
+        },
+        "modded": {
+          "lastVersionId": "fabric-loader-0.15.11-1.21",
+          "name": "Fabric",
+          "type": "custom",
+          "gameDir": "/home/player/fabric",
+          "javaArgs": "-Xmx4G  -XX:+UseG1GC",
+          "resolution": { "width": 1280, "height": 720 }
+        }
+      },
+      "settings": { "enableSnapshots": true, "profileSorting": "ByName", "soundOn": false },
+      "version": 3,
+      "clientToken": "keep-me"
+    });
+    let profiles: LauncherProfiles = serde_json::from_value(json).unwrap();
+    let version_list: RawVersionList = serde_json::from_value(
+      serde_json::json!({ "latest": { "release": "1.21", "snapshot": "24w14a" }, "versions": [] })
+    ).unwrap();
+
+    let latest = &profiles.profiles["b2f1c0"];
+    assert_eq!(latest.profile_type, Some(ProfileType::LatestRelease));
+    assert_eq!(latest.resolve_version_id(&version_list), Some(MCVersion::new("1.21")));
+
+    let modded = &profiles.profiles["modded"];
+    assert_eq!(modded.resolve_version_id(&version_list), Some(MCVersion::new("fabric-loader-0.15.11-1.21")));
+    let options = modded
+      .to_game_options(Path::new("/home/player/.minecraft"), Path::new("/usr/bin/java"), UserAuthentication::offline("Player"))
+      .unwrap();
+    assert_eq!(options.game_dir, Path::new("/home/player/fabric"));
+    assert_eq!(options.shared_dir(), Path::new("/home/player/.minecraft"));
+    assert_eq!(options.resolution, Some((1280, 720)));
+    assert_eq!(options.jvm_args, Some(vec!["-Xmx4G".to_string(), "-XX:+UseG1GC".to_string()]));
+
+    let written = serde_json::to_value(&profiles).unwrap();
+    assert_eq!(written["clientToken"], "keep-me");
+    assert_eq!(written["profiles"]["b2f1c0"]["skipJreVersionCheck"], true);
+    assert_eq!(written["settings"]["profileSorting"], "ByName");
+  }
+}
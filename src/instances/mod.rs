@@ -0,0 +1,432 @@
+use std::{ collections::HashMap, fs::{ self, File }, io, path::{ Component, Path, PathBuf } };
+
+use chrono::{ DateTime, Utc };
+use error::InstanceError;
+use log::{ info, warn };
+use serde::{ Deserialize, Serialize };
+use serde_json::Value;
+use zip::ZipArchive;
+
+use crate::{
+  bootstrap::{ auth::UserAuthentication, jvm::JvmOptionsBuilder, options::{ GameOptions, GameOptionsBuilder } },
+  java_manager::JavaRuntimeManager,
+  json::{ manifest::VersionManifest, MCVersion },
+  mod_loaders::{ fabric::FabricInstaller, forge::ForgeInstaller, neoforge::NeoForgeInstaller, quilt::QuiltInstaller },
+  version_manager::{ downloader::progress::ProgressReporter, VersionManager },
+};
+
+pub mod error;
+pub mod curseforge;
+pub mod launcher_profiles;
+pub mod mrpack;
+pub mod prism;
+
+/// File holding the settings of an instance, inside its directory
+pub const INSTANCE_FILE: &str = "instance.json";
+/// Directory the game runs in, inside the instance directory
+pub const GAME_DIR: &str = ".minecraft";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModLoaderKind {
+  Fabric,
+  Quilt,
+  Forge,
+  NeoForge,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModLoader {
+  pub kind: ModLoaderKind,
+  /// Version of the loader itself, e.g. `0.15.11` for Fabric or `47.2.0` for Forge
+  pub version: String,
+}
+
+impl ModLoader {
+  /// Id of the version usually created by the loader installer for the given Minecraft version.
+  ///
+  /// Legacy Forge installers use other schemes (e.g. `1.7.10-Forge10.13.4.1614-1.7.10`), so the id the installer
+  /// actually wrote is stored in `Instance::version_id` when the loader is installed through an instance.
+  pub fn version_id(&self, minecraft_version: &MCVersion) -> MCVersion {
+    let ModLoader { kind, version } = self;
+    match kind {
+      ModLoaderKind::Fabric => MCVersion::new(format!("fabric-loader-{version}-{minecraft_version}")),
+      ModLoaderKind::Quilt => MCVersion::new(format!("quilt-loader-{version}-{minecraft_version}")),
+      ModLoaderKind::Forge => MCVersion::new(format!("{minecraft_version}-forge-{version}")),
+      // NeoForge for 1.20.1 kept the Forge scheme, e.g. `1.20.1-47.1.106`
+      ModLoaderKind::NeoForge => match version.strip_prefix(&format!("{minecraft_version}-")) {
+        Some(forge_version) => MCVersion::new(format!("{minecraft_version}-forge-{forge_version}")),
+        None => MCVersion::new(format!("neoforge-{version}")),
+      },
+    }
+  }
+}
+
+/// A named game installation with its own game directory and launch settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Instance {
+  /// Name of the instance directory, unique inside the `InstanceManager`
+  pub id: String,
+  pub name: String,
+  pub minecraft_version: MCVersion,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub mod_loader: Option<ModLoader>,
+  /// Version to launch instead of the one derived from `minecraft_version` and `mod_loader`
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub version_id: Option<MCVersion>,
+  /// Java executable, the managed runtime required by the version is used if not set
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub java_path: Option<PathBuf>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub max_heap_mb: Option<u64>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub jvm_args: Vec<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub resolution: Option<(u32, u32)>,
+  pub created: DateTime<Utc>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub last_played: Option<DateTime<Utc>>,
+
+  /// Fields written by other tools, preserved when saving
+  #[serde(flatten)]
+  pub extra: HashMap<String, Value>,
+}
+
+impl Instance {
+  pub fn new(id: &str, name: &str, minecraft_version: MCVersion) -> Self {
+    Self {
+      id: id.to_string(),
+      name: name.to_string(),
+      minecraft_version,
+      mod_loader: None,
+      version_id: None,
+      java_path: None,
+      max_heap_mb: None,
+      jvm_args: vec![],
+      resolution: None,
+      created: Utc::now(),
+      last_played: None,
+      extra: HashMap::new(),
+    }
+  }
+
+  /// Id of the version launched by the instance
+  pub fn launch_version_id(&self) -> MCVersion {
+    if let Some(version_id) = &self.version_id {
+      return version_id.clone();
+    }
+    match &self.mod_loader {
+      Some(mod_loader) => mod_loader.version_id(&self.minecraft_version),
+      None => self.minecraft_version.clone(),
+    }
+  }
+}
+
+/// Stores instances as `<instances_dir>/<id>/instance.json`, each running in `<instances_dir>/<id>/.minecraft`
+#[derive(Debug, Clone)]
+pub struct InstanceManager {
+  pub instances_dir: PathBuf,
+}
+
+impl InstanceManager {
+  pub fn new(instances_dir: &Path) -> Self {
+    Self { instances_dir: instances_dir.to_path_buf() }
+  }
+
+  /// Directory of the instance. Unlike `get` and `save`, the id isn't checked for path separators or `..`
+  pub fn instance_dir(&self, id: &str) -> PathBuf {
+    self.instances_dir.join(id)
+  }
+
+  pub fn game_dir(&self, id: &str) -> PathBuf {
+    self.instance_dir(id).join(GAME_DIR)
+  }
+
+  /// Lists the instances sorted by name, skipping directories without a valid `instance.json`
+  pub fn list(&self) -> Result<Vec<Instance>, InstanceError> {
+    if !self.instances_dir.is_dir() {
+      return Ok(vec![]);
+    }
+    let mut instances = vec![];
+    for entry in fs::read_dir(&self.instances_dir)?.flatten() {
+      let Some(id) = entry.file_name().to_str().map(str::to_string) else {
+        continue;
+      };
+      if !entry.path().join(INSTANCE_FILE).is_file() {
+        continue;
+      }
+      match self.get(&id) {
+        Ok(instance) => instances.push(instance),
+        Err(err) => warn!("Skipping instance {id}: {err}"),
+      }
+    }
+    instances.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(instances)
+  }
+
+  pub fn get(&self, id: &str) -> Result<Instance, InstanceError> {
+    check_id(id)?;
+    let path = self.instance_dir(id).join(INSTANCE_FILE);
+    if !path.is_file() {
+      return Err(InstanceError::NotFound(id.to_string()));
+    }
+    let mut instance: Instance = serde_json::from_reader(File::open(path)?)?;
+    // The directory is the source of truth if the instance was moved by hand
+    instance.id = id.to_string();
+    Ok(instance)
+  }
+
+  pub fn save(&self, instance: &Instance) -> Result<(), InstanceError> {
+    check_id(&instance.id)?;
+    let instance_dir = self.instance_dir(&instance.id);
+    fs::create_dir_all(&instance_dir)?;
+    fs::write(instance_dir.join(INSTANCE_FILE), serde_json::to_vec_pretty(instance)?)?;
+    Ok(())
+  }
+
+  /// Creates and saves a new instance, with an id derived from its name
+  pub fn create(&self, name: &str, minecraft_version: MCVersion) -> Result<Instance, InstanceError> {
+    let instance = Instance::new(&self.unique_id(name), name, minecraft_version);
+    fs::create_dir_all(self.game_dir(&instance.id))?;
+    self.save(&instance)?;
+    info!("Created instance {} ({})", instance.name, instance.id);
+    Ok(instance)
+  }
+
+  /// Copies an instance, including its game directory, under a new name
+  pub fn clone_instance(&self, id: &str, name: &str) -> Result<Instance, InstanceError> {
+    let source = self.get(id)?;
+    let instance = Instance { id: self.unique_id(name), name: name.to_string(), created: Utc::now(), last_played: None, ..source };
+    copy_dir(&self.instance_dir(id), &self.instance_dir(&instance.id))?;
+    self.save(&instance)?;
+    Ok(instance)
+  }
+
+  /// Renames an instance, moving its directory to match the new name
+  pub fn rename(&self, id: &str, name: &str) -> Result<Instance, InstanceError> {
+    let mut instance = self.get(id)?;
+    if sanitize_id(name) != id {
+      let new_id = self.unique_id(name);
+      fs::rename(self.instance_dir(id), self.instance_dir(&new_id))?;
+      instance.id = new_id;
+    }
+    instance.name = name.to_string();
+    self.save(&instance)?;
+    Ok(instance)
+  }
+
+  pub fn delete(&self, id: &str) -> Result<(), InstanceError> {
+    check_id(id)?;
+    let instance_dir = self.instance_dir(id);
+    if !instance_dir.join(INSTANCE_FILE).is_file() {
+      return Err(InstanceError::NotFound(id.to_string()));
+    }
+    fs::remove_dir_all(instance_dir)?;
+    Ok(())
+  }
+
+  /// Deletes an instance whose import failed after it was created, returning the error that stopped it
+  pub(crate) fn discard(&self, instance: &Instance, err: InstanceError) -> InstanceError {
+    warn!("Removing instance {} after a failed import", instance.id);
+    if let Err(remove_err) = fs::remove_dir_all(self.instance_dir(&instance.id)) {
+      warn!("Failed to remove instance {}: {remove_err}", instance.id);
+    }
+    err
+  }
+
+  /// Resolves the version of the instance and builds the options to launch it.
+  ///
+  /// Libraries, assets and versions are shared in the `VersionManager` game directory. If the instance
+  /// has no Java path, the executable of the managed runtime under `<shared dir>/runtimes` is used,
+  /// it must be installed with `JavaRuntimeManager::install_runtime` before launching.
+  ///
+  /// # Errors
+  /// Returns an `InstanceError` if the version can't be resolved or the options are incomplete.
+  pub async fn prepare(
+    &self,
+    instance: &Instance,
+    authentication: UserAuthentication,
+    version_manager: &mut VersionManager
+  ) -> Result<(GameOptions, VersionManifest), InstanceError> {
+    check_id(&instance.id)?;
+    let manifest = version_manager.resolve_local_version(&instance.launch_version_id(), true, false).await?;
+    let shared_dir = &version_manager.game_dir;
+
+    let java_path = match (&instance.java_path, &manifest.java_version) {
+      (Some(java_path), _) => java_path.clone(),
+      (None, Some(java_version)) => {
+        let mut runtime_manager = JavaRuntimeManager::new(&shared_dir.join("runtimes"), &version_manager.client);
+        runtime_manager.os = version_manager.platform.os.clone();
+        runtime_manager.arch = version_manager.platform.arch;
+        runtime_manager.get_java_executable(&java_version.component)
+      }
+      (None, None) => {
+        return Err(InstanceError::NoJava(instance.id.clone()));
+      }
+    };
+
+    let mut jvm_options = JvmOptionsBuilder::default().extra_args(instance.jvm_args.clone());
+    if let Some(max_heap_mb) = instance.max_heap_mb {
+      jvm_options = jvm_options.max_heap_mb(max_heap_mb);
+    }
+
+    let mut options = GameOptionsBuilder::default()
+      .java_path(java_path)
+      .game_dir(self.game_dir(&instance.id))
+      .natives_dir(self.instance_dir(&instance.id).join("natives"))
+      .shared_dir(shared_dir.clone())
+      .platform(version_manager.platform.clone())
+      .authentication(authentication)
+      .jvm_options(jvm_options.build().map_err(|err| InstanceError::Options(err.to_string()))?);
+    if let Some(resolution) = instance.resolution {
+      options = options.resolution(resolution);
+    }
+    let options = options.build().map_err(|err| InstanceError::Options(err.to_string()))?;
+    Ok((options, manifest))
+  }
+
+  /// Turns a name into a directory name that isn't used yet
+  fn unique_id(&self, name: &str) -> String {
+    let base = sanitize_id(name);
+    let mut id = base.clone();
+    let mut suffix = 1;
+    while self.instance_dir(&id).exists() {
+      suffix += 1;
+      id = format!("{base} ({suffix})");
+    }
+    id
+  }
+}
+
+/// Rejects ids that aren't a single directory name, like `../other` or `a/b`
+fn check_id(id: &str) -> Result<(), InstanceError> {
+  let mut components = Path::new(id).components();
+  match (components.next(), components.next()) {
+    (Some(Component::Normal(_)), None) if !id.contains(['/', '\\']) => Ok(()),
+    _ => Err(InstanceError::InvalidId(id.to_string())),
+  }
+}
+
+fn sanitize_id(name: &str) -> String {
+  let id: String = name
+    .chars()
+    .map(|c| if c.is_alphanumeric() || "-_. ".contains(c) { c } else { '_' })
+    .collect();
+  let id = id.trim_matches(|c| c == '.' || c == ' ');
+  if id.is_empty() { "instance".to_string() } else { id.to_string() }
+}
+
+/// Joins a path read from an archive or a manifest to `base`, `None` if it would escape it
+pub(crate) fn enclosed_path(base: &Path, path: &str) -> Option<PathBuf> {
+  let path = Path::new(path);
+  path
+    .components()
+    .all(|component| matches!(component, Component::Normal(_)))
+    .then(|| base.join(path))
+}
+
+/// Extracts the files of the `prefix` directory of a modpack archive into `target`, returning their count
+pub(crate) fn extract_archive_dir(archive: &mut ZipArchive<File>, prefix: &str, target: &Path) -> Result<usize, InstanceError> {
+  let mut count = 0;
+  for i in 0..archive.len() {
+    let mut entry = archive.by_index(i)?;
+    let Some(path) = entry.enclosed_name() else {
+      warn!("Skipping unsafe entry {}", entry.name());
+      continue;
+    };
+    let Ok(relative) = path.strip_prefix(prefix) else {
+      continue;
+    };
+    if entry.is_dir() || relative.as_os_str().is_empty() {
+      continue;
+    }
+    let file = target.join(relative);
+    fs::create_dir_all(file.parent().unwrap_or(target))?;
+    io::copy(&mut entry, &mut File::create(file)?)?;
+    count += 1;
+  }
+  Ok(count)
+}
+
+/// Installs the loader of an imported instance, returning its resolved manifest.
+/// The id of the installed version is stored in `Instance::version_id`.
+///
+/// Forge and NeoForge are skipped without `java_path`, as their installer must run.
+pub(crate) async fn install_mod_loader(
+  version_manager: &mut VersionManager,
+  instance: &mut Instance,
+  java_path: Option<&Path>,
+  reporter: &ProgressReporter
+) -> Result<Option<VersionManifest>, InstanceError> {
+  let Some(ModLoader { kind, version }) = &instance.mod_loader else {
+    return Ok(None);
+  };
+  let client = version_manager.client.clone();
+  let minecraft_version = &instance.minecraft_version;
+  let manifest = match (kind, java_path) {
+    (ModLoaderKind::Fabric, _) => FabricInstaller::new(&client).install(version_manager, minecraft_version, version).await?,
+    (ModLoaderKind::Quilt, _) => QuiltInstaller::new(&client).install(version_manager, minecraft_version, version).await?,
+    (ModLoaderKind::Forge, Some(java_path)) => {
+      ForgeInstaller::new(&client).install(version_manager, minecraft_version, version, java_path, reporter).await?
+    }
+    (ModLoaderKind::NeoForge, Some(java_path)) => {
+      NeoForgeInstaller::new(&client).install(version_manager, minecraft_version, version, java_path, reporter).await?
+    }
+    (ModLoaderKind::Forge | ModLoaderKind::NeoForge, None) => {
+      warn!("{kind:?} {version} needs Java to run its installer, it must be installed");
+      return Ok(None);
+    }
+  };
+  instance.version_id = Some(manifest.id.clone());
+  Ok(Some(manifest))
+}
+
+pub(crate) fn copy_dir(source: &Path, target: &Path) -> std::io::Result<()> {
+  fs::create_dir_all(target)?;
+  for entry in fs::read_dir(source)?.flatten() {
+    let path = entry.path();
+    let target = target.join(entry.file_name());
+    if entry.file_type()?.is_dir() {
+      copy_dir(&path, &target)?;
+    } else {
+      fs::copy(&path, &target)?;
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::env::temp_dir;
+
+  use super::*;
+
+  #[test]
+  fn test_instances() {
+    let root = temp_dir().join(format!("mlc-instances-{}", std::process::id()));
+    let manager = InstanceManager::new(&root);
+
+    let mut instance = manager.create("Survival: 1.21", MCVersion::new("1.21")).unwrap();
+    assert_eq!(instance.id, "Survival_ 1.21");
+    instance.mod_loader = Some(ModLoader { kind: ModLoaderKind::Fabric, version: "0.15.11".into() });
+    manager.save(&instance).unwrap();
+    fs::write(manager.game_dir(&instance.id).join("options.txt"), "fov:1.0").unwrap();
+
+    let copy = manager.clone_instance(&instance.id, "Survival: 1.21").unwrap();
+    assert_eq!(copy.id, "Survival_ 1.21 (2)");
+    assert!(manager.game_dir(&copy.id).join("options.txt").is_file());
+    assert_eq!(copy.launch_version_id().to_string(), "fabric-loader-0.15.11-1.21");
+
+    let renamed = manager.rename(&copy.id, "Creative").unwrap();
+    let names: Vec<_> = manager.list().unwrap().into_iter().map(|i| i.name).collect();
+    assert_eq!(names, vec!["Creative", "Survival: 1.21"]);
+
+    assert!(matches!(manager.get("../Creative"), Err(InstanceError::InvalidId(_))));
+    assert!(matches!(manager.delete("Creative/.minecraft"), Err(InstanceError::InvalidId(_))));
+    assert!(matches!(manager.get(".."), Err(InstanceError::InvalidId(_))));
+    manager.delete(&renamed.id).unwrap();
+    assert!(matches!(manager.get(&renamed.id), Err(InstanceError::NotFound(_))));
+    let _ = fs::remove_dir_all(root);
+  }
+}
@@ -0,0 +1,417 @@
+use std::{ collections::HashMap, fs::{ self, File }, io::{ self, Read }, path::{ Path, PathBuf }, sync::Arc };
+
+use async_trait::async_trait;
+use log::{ info, warn };
+use reqwest::Client;
+use serde::{ Deserialize, Serialize };
+use sha2::{ Digest, Sha512 };
+use zip::ZipArchive;
+
+use crate::{
+  json::{ manifest::VersionManifest, MCVersion, Sha1Sum },
+  mod_loaders::neoforge::NeoForgeInstaller,
+  version_manager::{
+    downloader::{
+      download_job::DownloadJob,
+      downloadables::{ DownloadError, Downloadable, DownloadableMonitor, HashError, PreHashedDownloadable },
+      progress::ProgressReporter,
+    },
+    VersionManager,
+  },
+};
+
+use super::{ enclosed_path, error::InstanceError, extract_archive_dir, install_mod_loader, Instance, InstanceManager, ModLoader, ModLoaderKind };
+
+pub const MRPACK_INDEX: &str = "modrinth.index.json";
+/// Copied into the game directory of every side
+const OVERRIDES: &str = "overrides/";
+/// Copied after `overrides/` on the client, replacing its files
+const CLIENT_OVERRIDES: &str = "client-overrides/";
+
+/// The `modrinth.index.json` of a `.mrpack`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MrpackIndex {
+  pub format_version: u32,
+  pub game: String,
+  /// Version of the modpack
+  pub version_id: String,
+  pub name: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub summary: Option<String>,
+  #[serde(default)]
+  pub files: Vec<MrpackFile>,
+  /// `minecraft` and the loader, e.g. `fabric-loader`, mapped to their version
+  pub dependencies: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MrpackFile {
+  /// Destination, relative to the game directory
+  pub path: String,
+  /// Hex digests by algorithm, `sha1` and `sha512` are always present
+  pub hashes: HashMap<String, String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub env: Option<MrpackEnv>,
+  /// Mirrors of the file, tried in order
+  pub downloads: Vec<String>,
+  pub file_size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MrpackEnv {
+  pub client: EnvSupport,
+  pub server: EnvSupport,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EnvSupport {
+  Required,
+  /// Installed by default, the user may remove it
+  Optional,
+  Unsupported,
+}
+
+impl MrpackFile {
+  /// Files without `env` are installed on both sides
+  pub fn is_client_file(&self) -> bool {
+    self.env.as_ref().is_none_or(|env| env.client != EnvSupport::Unsupported)
+  }
+
+  /// Destination in `game_dir`, files escaping it are rejected
+  pub fn target(&self, game_dir: &Path) -> Result<PathBuf, InstanceError> {
+    enclosed_path(game_dir, &self.path).ok_or_else(|| InstanceError::InvalidModpack(format!("unsafe file path {}", self.path)))
+  }
+
+  fn hash(&self, algorithm: &str) -> Result<&str, InstanceError> {
+    self.hashes
+      .get(algorithm)
+      .map(String::as_str)
+      .ok_or_else(|| InstanceError::InvalidModpack(format!("{} has no {algorithm} hash", self.path)))
+  }
+}
+
+impl MrpackIndex {
+  pub fn minecraft_version(&self) -> Result<MCVersion, InstanceError> {
+    self.dependencies
+      .get("minecraft")
+      .map(MCVersion::new)
+      .ok_or_else(|| InstanceError::InvalidModpack("no minecraft dependency".to_string()))
+  }
+
+  pub fn mod_loader(&self) -> Option<ModLoader> {
+    self.dependencies.iter().find_map(|(id, version)| {
+      let (kind, version) = match id.as_str() {
+        "fabric-loader" => (ModLoaderKind::Fabric, version.clone()),
+        "quilt-loader" => (ModLoaderKind::Quilt, version.clone()),
+        "forge" => (ModLoaderKind::Forge, version.clone()),
+        "neoforge" => (ModLoaderKind::NeoForge, NeoForgeInstaller::normalize_version(&self.minecraft_version().ok()?, version)),
+        _ => return None,
+      };
+      Some(ModLoader { kind, version })
+    })
+  }
+
+  pub fn client_files(&self) -> impl Iterator<Item = &MrpackFile> {
+    self.files.iter().filter(|file| file.is_client_file())
+  }
+}
+
+/// An opened `.mrpack` file
+pub struct Mrpack {
+  pub path: PathBuf,
+  pub index: MrpackIndex,
+  archive: ZipArchive<File>,
+}
+
+impl Mrpack {
+  pub fn open(path: &Path) -> Result<Self, InstanceError> {
+    let mut archive = ZipArchive::new(File::open(path)?)?;
+    let mut index = String::new();
+    archive
+      .by_name(MRPACK_INDEX)
+      .map_err(|_| InstanceError::InvalidModpack(format!("missing {MRPACK_INDEX}")))?
+      .read_to_string(&mut index)?;
+    let index: MrpackIndex = serde_json::from_str(&index)?;
+    if index.format_version != 1 || index.game != "minecraft" {
+      return Err(InstanceError::InvalidModpack(format!("unsupported format {} for {}", index.format_version, index.game)));
+    }
+    Ok(Self { path: path.to_path_buf(), index, archive })
+  }
+
+  /// Extracts `overrides/` then `client-overrides/` into `game_dir`, returning the number of files written
+  pub fn extract_overrides(&mut self, game_dir: &Path) -> Result<usize, InstanceError> {
+    Ok(extract_archive_dir(&mut self.archive, OVERRIDES, game_dir)? + extract_archive_dir(&mut self.archive, CLIENT_OVERRIDES, game_dir)?)
+  }
+}
+
+/// The result of importing a `.mrpack`
+#[derive(Debug, Clone)]
+pub struct MrpackImport {
+  pub instance: Instance,
+  /// Manifest of the installed loader, `None` for vanilla modpacks or when the loader must be installed
+  pub manifest: Option<VersionManifest>,
+}
+
+/// A file of the modpack, downloaded from the next mirror when the previous one fails
+struct MirroredDownloadable {
+  /// One per mirror, sharing the same target and monitor
+  mirrors: Vec<PreHashedDownloadable>,
+}
+
+impl MirroredDownloadable {
+  /// `urls` must not be empty
+  fn new(urls: &[String], target_file: &Path, expected_hash: Sha1Sum) -> Self {
+    let mut mirrors: Vec<_> = urls.iter().map(|url| PreHashedDownloadable::new(url, target_file, expected_hash.clone())).collect();
+    let monitor = Arc::clone(&mirrors[0].monitor);
+    mirrors.iter_mut().for_each(|mirror| mirror.monitor = Arc::clone(&monitor));
+    Self { mirrors }
+  }
+}
+
+#[async_trait]
+impl Downloadable for MirroredDownloadable {
+  fn url(&self) -> &String {
+    self.mirrors[0].url()
+  }
+
+  fn get_target_file(&self) -> &PathBuf {
+    self.mirrors[0].get_target_file()
+  }
+
+  fn get_status(&self) -> String {
+    self.mirrors[0].get_status()
+  }
+
+  fn get_monitor(&self) -> &Arc<DownloadableMonitor> {
+    self.mirrors[0].get_monitor()
+  }
+
+  fn get_start_time(&self) -> Option<u64> {
+    self.mirrors[0].get_start_time()
+  }
+
+  fn set_start_time(&self, start_time: u64) {
+    self.mirrors[0].set_start_time(start_time);
+  }
+
+  fn get_end_time(&self) -> Option<u64> {
+    self.mirrors[0].get_end_time()
+  }
+
+  fn set_end_time(&self, end_time: u64) {
+    self.mirrors[0].set_end_time(end_time);
+  }
+
+  async fn get_expected_hash(&self, client: &Client) -> Result<Option<Vec<u8>>, HashError> {
+    self.mirrors[0].get_expected_hash(client).await
+  }
+
+  async fn download(&self, client: &Client) -> Result<(), DownloadError> {
+    let mut result = Ok(());
+    for mirror in &self.mirrors {
+      result = mirror.download(client).await;
+      match &result {
+        Ok(()) => break,
+        Err(err) => warn!("Couldn't download {} from {}: {err}", mirror.target_file.display(), mirror.url),
+      }
+    }
+    result
+  }
+}
+
+/// Creates an instance from a Modrinth modpack.
+///
+/// The client files are downloaded from their mirrors, in order, and checked against their SHA-1 and SHA-512,
+/// then the overrides are extracted. Fabric and Quilt are installed with the `VersionManager`, Forge and NeoForge
+/// only if `java_path` is set as their installer must run. The instance is removed if any of this fails.
+///
+/// # Errors
+/// Returns an `InstanceError` if the modpack is invalid, a file can't be downloaded or the loader can't be installed.
+pub async fn import(
+  path: &Path,
+  instances: &InstanceManager,
+  version_manager: &mut VersionManager,
+  java_path: Option<&Path>,
+  reporter: &ProgressReporter
+) -> Result<MrpackImport, InstanceError> {
+  let mut mrpack = Mrpack::open(path)?;
+  let index = mrpack.index.clone();
+  let minecraft_version = index.minecraft_version()?;
+  info!("Importing {} {} for {}", index.name, index.version_id, minecraft_version);
+
+  // Checked before creating the instance, so most invalid modpacks fail without writing anything
+  let mut files = vec![];
+  for file in index.client_files() {
+    let invalid = |reason: &str| InstanceError::InvalidModpack(format!("{} {reason}", file.path));
+    if file.downloads.is_empty() {
+      return Err(invalid("has no download"));
+    }
+    let sha1 = Sha1Sum::try_from(file.hash("sha1")?.to_string()).map_err(|_| invalid("has an invalid sha1"))?;
+    files.push((file.target(Path::new(""))?, &file.downloads, sha1, file.hash("sha512")?.to_lowercase()));
+  }
+
+  let mut instance = instances.create(&index.name, minecraft_version.clone())?;
+  instance.mod_loader = index.mod_loader();
+  let game_dir = instances.game_dir(&instance.id);
+
+  let mut downloadables: Vec<Box<dyn Downloadable + Send + Sync>> = vec![];
+  let mut sha512s = vec![];
+  for (path, urls, sha1, sha512) in files {
+    let target = game_dir.join(path);
+    downloadables.push(Box::new(MirroredDownloadable::new(urls, &target, sha1)));
+    sha512s.push((target, sha512));
+  }
+  let installed = async {
+    DownloadJob::new("Modpack files")
+      .with_client(version_manager.client.clone())
+      .with_progress_reporter(reporter)
+      .add_downloadables(downloadables)
+      .start().await?;
+    check_sha512s(sha512s)?;
+    mrpack.extract_overrides(&game_dir)?;
+
+    let manifest = install_mod_loader(version_manager, &mut instance, java_path, reporter).await?;
+    instances.save(&instance)?;
+    Ok(manifest)
+  }.await;
+
+  match installed {
+    Ok(manifest) => Ok(MrpackImport { instance, manifest }),
+    Err(err) => Err(instances.discard(&instance, err)),
+  }
+}
+
+fn check_sha512s(sha512s: Vec<(PathBuf, String)>) -> Result<(), InstanceError> {
+  for (target, expected) in sha512s {
+    let mut hasher = Sha512::new();
+    io::copy(&mut File::open(&target)?, &mut hasher)?;
+    if hex::encode(hasher.finalize()) != expected {
+      let _ = fs::remove_file(&target);
+      return Err(InstanceError::ChecksumMismatch(target));
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{ env::temp_dir, io::Write };
+
+  use sha1::Sha1;
+  use zip::{ write::SimpleFileOptions, ZipWriter };
+
+  use crate::{ json::EnvironmentFeatures, tests::serve_files, version_manager::downloader::progress::EmptyReporter };
+
+  use super::*;
+
+  fn write_mrpack(path: &Path, entries: &[(&str, String)]) {
+    let mut zip = ZipWriter::new(File::create(path).unwrap());
+    for (name, contents) in entries {
+      zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+      zip.write_all(contents.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+  }
+
+  #[tokio::test]
+  async fn test_import_mrpack() {
+    let root = temp_dir().join(format!("mlc-mrpack-{}", std::process::id()));
+    fs::create_dir_all(&root).unwrap();
+    let path = root.join("pack.mrpack");
+
+    let index = serde_json::json!({
+      "formatVersion": 1,
+      "game": "minecraft",
+      "versionId": "1.0.0",
+      "name": "Server Pack",
+      "files": [{
+        "path": "mods/server-only.jar",
+        "hashes": { "sha1": "a9993e364706816aba3e25717850c26c9cd0d89d", "sha512": "ddaf35a1" },
+        "env": { "client": "unsupported", "server": "required" },
+        "downloads": ["https://cdn.modrinth.com/data/server-only.jar"],
+        "fileSize": 3
+      }],
+      "dependencies": { "minecraft": "1.20.1" }
+    });
+    write_mrpack(&path, &[
+      (MRPACK_INDEX, index.to_string()),
+      ("overrides/config/mod.toml", "a = 1".to_string()),
+      ("overrides/options.txt", "fov:0.0".to_string()),
+      ("client-overrides/options.txt", "fov:1.0".to_string()),
+    ]);
+
+    let instances = InstanceManager::new(&root.join("instances"));
+    let mut version_manager = VersionManager::new(&root.join("shared"), &EnvironmentFeatures::default(), None);
+    let reporter: ProgressReporter = Arc::new(EmptyReporter);
+    let import = import(&path, &instances, &mut version_manager, None, &reporter).await.unwrap();
+
+    assert_eq!(import.instance.name, "Server Pack");
+    assert!(import.manifest.is_none());
+    let game_dir = instances.game_dir(&import.instance.id);
+    assert!(!game_dir.join("mods").join("server-only.jar").exists());
+    assert_eq!(fs::read_to_string(game_dir.join("options.txt")).unwrap(), "fov:1.0");
+    assert!(game_dir.join("config").join("mod.toml").is_file());
+
+    let mut unsafe_file: MrpackFile = serde_json::from_value(index["files"][0].clone()).unwrap();
+    unsafe_file.path = "../evil.jar".to_string();
+    assert!(unsafe_file.target(&game_dir).is_err());
+
+    let mut fabric_index: MrpackIndex = serde_json::from_value(index).unwrap();
+    fabric_index.dependencies.insert("fabric-loader".to_string(), "0.15.11".to_string());
+    assert_eq!(fabric_index.mod_loader(), Some(ModLoader { kind: ModLoaderKind::Fabric, version: "0.15.11".to_string() }));
+    fabric_index.dependencies.remove("fabric-loader");
+    fabric_index.dependencies.insert("neoforge".to_string(), "47.1.106".to_string());
+    assert_eq!(fabric_index.mod_loader(), Some(ModLoader { kind: ModLoaderKind::NeoForge, version: "1.20.1-47.1.106".to_string() }));
+
+    let _ = fs::remove_dir_all(root);
+  }
+
+  #[tokio::test]
+  async fn test_import_mrpack_downloads() {
+    let root = temp_dir().join(format!("mlc-mrpack-downloads-{}", std::process::id()));
+    fs::create_dir_all(&root).unwrap();
+    let path = root.join("pack.mrpack");
+
+    let contents = b"mod contents".to_vec();
+    let url = serve_files(HashMap::from([("/mod.jar".to_string(), contents.clone())]));
+    let sha1 = hex::encode(Sha1::digest(&contents));
+    let sha512 = hex::encode(Sha512::digest(&contents));
+    let pack = |name: &str, sha512: &str| {
+      serde_json::json!({
+        "formatVersion": 1,
+        "game": "minecraft",
+        "versionId": "1.0.0",
+        "name": name,
+        "files": [{
+          "path": "mods/mod.jar",
+          "hashes": { "sha1": sha1, "sha512": sha512 },
+          // The first mirror is down
+          "downloads": [format!("{url}/missing/mod.jar"), format!("{url}/mod.jar")],
+          "fileSize": contents.len()
+        }],
+        "dependencies": { "minecraft": "1.20.1" }
+      }).to_string()
+    };
+
+    let instances = InstanceManager::new(&root.join("instances"));
+    let mut version_manager = VersionManager::new(&root.join("shared"), &EnvironmentFeatures::default(), None);
+    let reporter: ProgressReporter = Arc::new(EmptyReporter);
+    write_mrpack(&path, &[(MRPACK_INDEX, pack("Mirrored", &sha512))]);
+    let imported = import(&path, &instances, &mut version_manager, None, &reporter).await.unwrap();
+    let target = instances.game_dir(&imported.instance.id).join("mods").join("mod.jar");
+    assert_eq!(fs::read(target).unwrap(), contents);
+
+    // A file matching its SHA-1 but not its SHA-512 fails the import and removes the instance
+    write_mrpack(&path, &[(MRPACK_INDEX, pack("Tampered", &"0".repeat(128)))]);
+    let err = import(&path, &instances, &mut version_manager, None, &reporter).await.unwrap_err();
+    assert!(matches!(err, InstanceError::ChecksumMismatch(_)));
+    let names: Vec<_> = instances.list().unwrap().into_iter().map(|instance| instance.name).collect();
+    assert_eq!(names, vec!["Mirrored"]);
+    assert_eq!(fs::read_dir(root.join("instances")).unwrap().count(), 1);
+
+    let _ = fs::remove_dir_all(root);
+  }
+}
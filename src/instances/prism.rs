@@ -0,0 +1,502 @@
+use std::{ collections::HashMap, fs::{ self, File }, path::{ Path, PathBuf } };
+
+use chrono::{ TimeZone, Utc };
+use log::{ info, warn };
+use serde::{ Deserialize, Serialize };
+
+use crate::{
+  json::{ manifest::{ library::Library, VersionManifest }, MCVersion, ReleaseType },
+  mod_loaders::neoforge::NeoForgeInstaller,
+  version_manager::{ downloader::progress::ProgressReporter, VersionManager },
+};
+
+use super::{ copy_dir, error::InstanceError, install_mod_loader, Instance, InstanceManager, ModLoader, ModLoaderKind };
+
+pub const INSTANCE_CFG: &str = "instance.cfg";
+pub const MMC_PACK: &str = "mmc-pack.json";
+
+const MINECRAFT_UID: &str = "net.minecraft";
+/// Components already covered by the vanilla manifest or by the loader
+const BUNDLED_UIDS: &[&str] = &["org.lwjgl", "org.lwjgl3", "net.fabricmc.intermediary", "org.quiltmc.hashed"];
+
+/// How the game directory of an imported instance is brought over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+  Copy,
+  /// Symlinks the original game directory, both launchers then share the same saves and mods
+  Link,
+}
+
+/// The `mmc-pack.json` file listing the components of an instance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MmcPack {
+  #[serde(default)]
+  pub components: Vec<PrismComponent>,
+  #[serde(default)]
+  pub format_version: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrismComponent {
+  pub uid: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub version: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub cached_version: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub cached_name: Option<String>,
+  #[serde(default)]
+  pub dependency_only: bool,
+}
+
+impl PrismComponent {
+  pub fn get_version(&self) -> Option<&str> {
+    self.version.as_deref().or(self.cached_version.as_deref())
+  }
+
+  fn mod_loader_kind(&self) -> Option<ModLoaderKind> {
+    match self.uid.as_str() {
+      "net.fabricmc.fabric-loader" => Some(ModLoaderKind::Fabric),
+      "org.quiltmc.quilt-loader" => Some(ModLoaderKind::Quilt),
+      "net.minecraftforge" => Some(ModLoaderKind::Forge),
+      "net.neoforged" => Some(ModLoaderKind::NeoForge),
+      _ => None,
+    }
+  }
+}
+
+/// The parts of a cached Prism meta file (`meta/<uid>/<version>.json`) that map to a version manifest
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ComponentMeta {
+  #[serde(default)]
+  main_class: Option<String>,
+  #[serde(default)]
+  libraries: Vec<Library>,
+  #[serde(default, rename = "+tweakers")]
+  tweakers: Vec<String>,
+  #[serde(default, rename = "+jvmArgs")]
+  jvm_args: Vec<String>,
+  #[serde(default)]
+  jar_mods: Vec<serde_json::Value>,
+  /// Components the version depends on, e.g. the LWJGL version of a Minecraft version
+  #[serde(default)]
+  requires: Vec<ComponentRequirement>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ComponentRequirement {
+  uid: String,
+  #[serde(default)]
+  equals: Option<String>,
+  #[serde(default)]
+  suggests: Option<String>,
+}
+
+/// A component the importer couldn't map
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedComponent {
+  pub uid: String,
+  pub version: Option<String>,
+  pub reason: String,
+}
+
+/// The result of importing a Prism Launcher / MultiMC instance
+#[derive(Debug, Clone)]
+pub struct PrismImport {
+  pub instance: Instance,
+  /// Manifest installed for the loader components, `None` for vanilla instances, when the Fabric or Quilt
+  /// loader isn't cached by Prism, or when Forge and NeoForge couldn't be installed without a Java path
+  pub manifest: Option<VersionManifest>,
+  pub unsupported: Vec<UnsupportedComponent>,
+}
+
+/// A Prism Launcher / MultiMC instance directory
+#[derive(Debug, Clone)]
+pub struct PrismInstance {
+  pub dir: PathBuf,
+  /// Values of `instance.cfg`
+  pub config: HashMap<String, String>,
+  pub pack: MmcPack,
+}
+
+impl PrismInstance {
+  pub fn load(dir: &Path) -> Result<Self, InstanceError> {
+    let cfg_path = dir.join(INSTANCE_CFG);
+    if !cfg_path.is_file() {
+      return Err(InstanceError::InvalidPrismInstance(format!("{} not found", cfg_path.display())));
+    }
+    let config = parse_instance_cfg(&fs::read_to_string(cfg_path)?);
+
+    let pack_path = dir.join(MMC_PACK);
+    if !pack_path.is_file() {
+      return Err(InstanceError::InvalidPrismInstance(format!("{} not found, instances from MultiMC 0.6 and older aren't supported", pack_path.display())));
+    }
+    let pack = serde_json::from_reader(File::open(pack_path)?)?;
+    Ok(Self { dir: dir.to_path_buf(), config, pack })
+  }
+
+  pub fn name(&self) -> String {
+    self.config
+      .get("name")
+      .cloned()
+      .unwrap_or_else(|| self.dir.file_name().unwrap_or_default().to_string_lossy().to_string())
+  }
+
+  /// The game directory, `.minecraft` for Prism and `minecraft` for older MultiMC instances
+  pub fn minecraft_dir(&self) -> Option<PathBuf> {
+    [".minecraft", "minecraft"]
+      .iter()
+      .map(|name| self.dir.join(name))
+      .find(|dir| dir.is_dir())
+  }
+
+  pub fn minecraft_version(&self) -> Result<MCVersion, InstanceError> {
+    self.pack.components
+      .iter()
+      .find(|component| component.uid == MINECRAFT_UID)
+      .and_then(PrismComponent::get_version)
+      .map(MCVersion::new)
+      .ok_or_else(|| InstanceError::InvalidPrismInstance(format!("no {MINECRAFT_UID} component")))
+  }
+
+  pub fn mod_loader(&self) -> Option<ModLoader> {
+    let minecraft_version = self.minecraft_version().ok()?;
+    self.pack.components.iter().find_map(|component| {
+      let (kind, version) = (component.mod_loader_kind()?, component.get_version()?);
+      let version = match kind {
+        ModLoaderKind::NeoForge => NeoForgeInstaller::normalize_version(&minecraft_version, version),
+        _ => version.to_string(),
+      };
+      Some(ModLoader { kind, version })
+    })
+  }
+
+  /// Reads the cached Prism meta file of a component
+  fn component_meta(meta_dir: Option<&Path>, uid: &str, version: Option<&str>) -> Result<Option<ComponentMeta>, InstanceError> {
+    let path = version
+      .zip(meta_dir)
+      .map(|(version, meta_dir)| meta_dir.join(uid).join(format!("{version}.json")))
+      .filter(|path| path.is_file());
+    match path {
+      Some(path) => Ok(Some(serde_json::from_reader(File::open(path)?)?)),
+      None => Ok(None),
+    }
+  }
+
+  fn config_flag(&self, key: &str) -> bool {
+    self.config.get(key).is_some_and(|value| value == "true")
+  }
+
+  /// Builds a manifest inheriting from the vanilla version out of the components cached in `meta_dir`.
+  ///
+  /// Forge and NeoForge aren't mapped as their installer must run, `import` installs them. LWJGL components
+  /// pinning another version than the vanilla one are reported as unsupported. Returns `None` if there's
+  /// nothing to add to the vanilla version or the loader metadata isn't cached.
+  pub fn to_manifest(&self, meta_dir: Option<&Path>) -> Result<(Option<VersionManifest>, Vec<UnsupportedComponent>), InstanceError> {
+    let minecraft_version = self.minecraft_version()?;
+    let minecraft_meta = Self::component_meta(meta_dir, MINECRAFT_UID, Some(&minecraft_version.to_string()))?;
+    let mut unsupported = vec![];
+    let mut libraries = vec![];
+    let mut main_class = None;
+    let mut missing_meta = false;
+
+    for component in &self.pack.components {
+      let uid = component.uid.as_str();
+      let kind = component.mod_loader_kind();
+      let unsupported_component = |reason: &str| UnsupportedComponent {
+        uid: uid.to_string(),
+        version: component.get_version().map(str::to_string),
+        reason: reason.to_string(),
+      };
+
+      if uid == MINECRAFT_UID || matches!(kind, Some(ModLoaderKind::Forge | ModLoaderKind::NeoForge)) {
+        continue;
+      }
+      if kind.is_none() && !BUNDLED_UIDS.contains(&uid) {
+        unsupported.push(unsupported_component("unknown component"));
+        continue;
+      }
+      // LWJGL comes with the vanilla manifest, only its own version can be used
+      if uid.starts_with("org.lwjgl") {
+        let vanilla = minecraft_meta
+          .iter()
+          .flat_map(|meta| &meta.requires)
+          .find(|requirement| requirement.uid == uid)
+          .and_then(|requirement| requirement.equals.as_deref().or(requirement.suggests.as_deref()));
+        match (vanilla, component.get_version()) {
+          (Some(vanilla), Some(version)) if vanilla != version => {
+            unsupported.push(unsupported_component(&format!("pins LWJGL {version}, the vanilla version uses {vanilla}")));
+          }
+          // Added by Prism for the Minecraft version, not chosen by the user
+          (None, _) if !component.dependency_only => {
+            unsupported.push(unsupported_component("the LWJGL version can't be checked against the vanilla version"));
+          }
+          _ => {}
+        }
+        continue;
+      }
+
+      let Some(meta) = Self::component_meta(meta_dir, uid, component.get_version())? else {
+        missing_meta = true;
+        continue;
+      };
+      if !meta.tweakers.is_empty() || !meta.jvm_args.is_empty() || !meta.jar_mods.is_empty() {
+        unsupported.push(unsupported_component("tweakers, JVM arguments and jar mods aren't supported"));
+        continue;
+      }
+      libraries.extend(meta.libraries);
+      if meta.main_class.is_some() {
+        main_class = meta.main_class;
+      }
+    }
+
+    let Some(mod_loader) = self.mod_loader().filter(|loader| matches!(loader.kind, ModLoaderKind::Fabric | ModLoaderKind::Quilt)) else {
+      return Ok((None, unsupported));
+    };
+    if missing_meta || main_class.is_none() {
+      warn!("{:?} {} isn't cached by Prism, it must be installed", mod_loader.kind, mod_loader.version);
+      return Ok((None, unsupported));
+    }
+
+    let now = Utc.timestamp_opt(Utc::now().timestamp(), 0).unwrap().fixed_offset();
+    let manifest = VersionManifest {
+      id: mod_loader.version_id(&minecraft_version),
+      inherits_from: Some(minecraft_version),
+      main_class,
+      libraries,
+      release_time: now.into(),
+      updated_time: now.into(),
+      release_type: ReleaseType::Release,
+      arguments: HashMap::new(),
+      minecraft_arguments: None,
+      asset_index: None,
+      assets: None,
+      compatibility_rules: vec![],
+      compliance_level: None,
+      downloads: HashMap::new(),
+      java_version: None,
+      logging: HashMap::new(),
+      jar: None,
+      minimum_launcher_version: None,
+    };
+    Ok((Some(manifest), unsupported))
+  }
+}
+
+/// Imports a Prism Launcher / MultiMC instance.
+///
+/// Fabric and Quilt manifests are built from Prism's `meta` cache next to its `instances` directory and installed with
+/// the `VersionManager`, whose directory is shared by every instance. Forge and NeoForge are installed with their
+/// installer if `java_path` is set, and reported as unsupported otherwise. The instance is removed if the import fails.
+///
+/// # Errors
+/// Returns an `InstanceError` if the instance is invalid, its files can't be copied or the loader can't be installed.
+pub async fn import(
+  source: &Path,
+  instances: &InstanceManager,
+  version_manager: &mut VersionManager,
+  mode: ImportMode,
+  java_path: Option<&Path>,
+  reporter: &ProgressReporter
+) -> Result<PrismImport, InstanceError> {
+  let prism = PrismInstance::load(source)?;
+  // <prism root>/instances/<instance>
+  let meta_dir = source.parent().and_then(Path::parent).map(|root| root.join("meta"));
+  let (manifest, mut unsupported) = prism.to_manifest(meta_dir.as_deref())?;
+  let mod_loader = prism.mod_loader();
+  let needs_installer = mod_loader.as_ref().is_some_and(|loader| matches!(loader.kind, ModLoaderKind::Forge | ModLoaderKind::NeoForge));
+  if needs_installer && java_path.is_none() {
+    let component = prism.pack.components.iter().find(|component| component.mod_loader_kind() == mod_loader.as_ref().map(|loader| loader.kind));
+    unsupported.extend(component.map(|component| UnsupportedComponent {
+      uid: component.uid.clone(),
+      version: component.get_version().map(str::to_string),
+      reason: "needs a Java path to run its installer".to_string(),
+    }));
+  }
+  for component in &unsupported {
+    warn!("Unsupported component {} {:?}: {}", component.uid, component.version, component.reason);
+  }
+  if let Some(manifest) = &manifest {
+    version_manager.install_local_version(manifest)?;
+  }
+
+  let mut instance = instances.create(&prism.name(), prism.minecraft_version()?)?;
+  instance.mod_loader = mod_loader;
+  instance.version_id = manifest.as_ref().map(|manifest| manifest.id.clone());
+  apply_config(&prism, &mut instance);
+  let installed = async {
+    import_game_dir(&prism, &instance, instances, mode)?;
+    let installed = match (needs_installer, java_path) {
+      (true, Some(java_path)) => install_mod_loader(version_manager, &mut instance, Some(java_path), reporter).await?,
+      _ => None,
+    };
+    instances.save(&instance)?;
+    Ok(installed)
+  }.await;
+
+  match installed {
+    Ok(installed) => Ok(PrismImport { instance, manifest: installed.or(manifest), unsupported }),
+    Err(err) => Err(instances.discard(&instance, err)),
+  }
+}
+
+/// Copies the Java, memory and window settings overridden by the Prism instance
+fn apply_config(prism: &PrismInstance, instance: &mut Instance) {
+  let config = &prism.config;
+  if prism.config_flag("OverrideJavaLocation") || prism.config_flag("OverrideJava") {
+    instance.java_path = config.get("JavaPath").filter(|path| !path.is_empty()).map(PathBuf::from);
+  }
+  if prism.config_flag("OverrideMemory") {
+    instance.max_heap_mb = config.get("MaxMemAlloc").and_then(|value| value.parse().ok());
+  }
+  if prism.config_flag("OverrideJavaArgs") {
+    instance.jvm_args = config.get("JvmArgs").map(|args| args.split_whitespace().map(str::to_string).collect()).unwrap_or_default();
+  }
+  if prism.config_flag("OverrideWindow") {
+    let size = |key: &str| config.get(key).and_then(|value| value.parse().ok());
+    instance.resolution = size("MinecraftWinWidth").zip(size("MinecraftWinHeight"));
+  }
+  instance.last_played = config
+    .get("lastLaunchTime")
+    .and_then(|value| value.parse().ok())
+    .and_then(|millis| Utc.timestamp_millis_opt(millis).single());
+}
+
+fn import_game_dir(prism: &PrismInstance, instance: &Instance, instances: &InstanceManager, mode: ImportMode) -> Result<(), InstanceError> {
+  if let Some(minecraft_dir) = prism.minecraft_dir() {
+    let game_dir = instances.game_dir(&instance.id);
+    info!("Importing {} to {}", minecraft_dir.display(), game_dir.display());
+    match mode {
+      ImportMode::Copy => copy_dir(&minecraft_dir, &game_dir)?,
+      ImportMode::Link => {
+        fs::remove_dir(&game_dir)?;
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(minecraft_dir.canonicalize()?, &game_dir)?;
+        #[cfg(windows)]
+        std::os::windows::fs::symlink_dir(minecraft_dir.canonicalize()?, &game_dir)?;
+      }
+    }
+  }
+  Ok(())
+}
+
+/// Parses the `key=value` lines of an `instance.cfg`, ignoring sections and comments
+pub fn parse_instance_cfg(contents: &str) -> HashMap<String, String> {
+  contents
+    .lines()
+    .map(str::trim)
+    .filter(|line| !line.starts_with('[') && !line.starts_with('#') && !line.starts_with(';'))
+    .filter_map(|line| line.split_once('='))
+    .map(|(key, value)| {
+      let value = value.trim();
+      // Qt quotes values containing special characters
+      let value = match value.strip_prefix('"').and_then(|value| value.strip_suffix('"')) {
+        Some(quoted) => quoted.replace("\\\"", "\"").replace("\\\\", "\\"),
+        None => value.to_string(),
+      };
+      (key.trim().to_string(), value)
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use std::env::temp_dir;
+
+  use std::sync::Arc;
+
+  use crate::{ json::EnvironmentFeatures, version_manager::downloader::progress::EmptyReporter };
+
+  use super::*;
+
+  #[tokio::test]
+  async fn test_import_prism() {
+    let root = temp_dir().join(format!("mlc-prism-{}", std::process::id()));
+    let source = root.join("prism").join("instances").join("Fabric 1.20.1");
+    let meta_dir = root.join("prism").join("meta");
+    fs::create_dir_all(source.join(".minecraft").join("mods")).unwrap();
+    fs::create_dir_all(meta_dir.join("net.fabricmc.fabric-loader")).unwrap();
+    fs::create_dir_all(meta_dir.join("net.fabricmc.intermediary")).unwrap();
+
+    fs::write(
+      source.join(INSTANCE_CFG),
+      "[General]\nname=\"Fabric, 1.20.1\"\nOverrideMemory=true\nMaxMemAlloc=4096\nOverrideJavaArgs=true\nJvmArgs=-Dfoo=bar\n"
+    ).unwrap();
+    let pack = serde_json::json!({
+      "formatVersion": 1,
+      "components": [
+        { "uid": "org.lwjgl3", "version": "3.3.1", "dependencyOnly": true },
+        { "uid": "net.minecraft", "version": "1.20.1", "important": true },
+        { "uid": "net.fabricmc.intermediary", "version": "1.20.1", "dependencyOnly": true },
+        { "uid": "net.fabricmc.fabric-loader", "version": "0.15.11" },
+        { "uid": "com.mumfrey.liteloader", "version": "1.12.2" }
+      ]
+    });
+    fs::write(source.join(MMC_PACK), pack.to_string()).unwrap();
+    fs::write(
+      meta_dir.join("net.fabricmc.fabric-loader").join("0.15.11.json"),
+      r#"{ "mainClass": "net.fabricmc.loader.impl.launch.knot.KnotClient",
+           "libraries": [{ "name": "net.fabricmc:fabric-loader:0.15.11", "url": "https://maven.fabricmc.net/" }] }"#
+    ).unwrap();
+    fs::write(
+      meta_dir.join("net.fabricmc.intermediary").join("1.20.1.json"),
+      r#"{ "libraries": [{ "name": "net.fabricmc:intermediary:1.20.1", "url": "https://maven.fabricmc.net/" }] }"#
+    ).unwrap();
+
+    let shared_dir = root.join("shared");
+    let instances = InstanceManager::new(&root.join("instances"));
+    let mut version_manager = VersionManager::new(&shared_dir, &EnvironmentFeatures::default(), None);
+    let reporter: ProgressReporter = Arc::new(EmptyReporter);
+    let import = import(&source, &instances, &mut version_manager, ImportMode::Copy, None, &reporter).await.unwrap();
+
+    assert_eq!(import.instance.name, "Fabric, 1.20.1");
+    assert_eq!(import.instance.max_heap_mb, Some(4096));
+    assert_eq!(import.instance.jvm_args, vec!["-Dfoo=bar"]);
+    assert_eq!(import.instance.launch_version_id().to_string(), "fabric-loader-0.15.11-1.20.1");
+    assert!(instances.game_dir(&import.instance.id).join("mods").is_dir());
+
+    let manifest = import.manifest.unwrap();
+    assert_eq!(manifest.inherits_from, Some(MCVersion::new("1.20.1")));
+    assert_eq!(manifest.libraries.len(), 2);
+    assert!(version_manager.get_installed_version(&manifest.id).is_ok());
+    assert_eq!(import.unsupported.len(), 1);
+    assert_eq!(import.unsupported[0].uid, "com.mumfrey.liteloader");
+
+    // LWJGL pinned to another version than the one Prism lists for the Minecraft version
+    fs::create_dir_all(meta_dir.join(MINECRAFT_UID)).unwrap();
+    fs::write(meta_dir.join(MINECRAFT_UID).join("1.20.1.json"), r#"{ "requires": [{ "suggests": "3.3.1", "uid": "org.lwjgl3" }] }"#).unwrap();
+    let mut lwjgl = PrismInstance::load(&source).unwrap();
+    let (_, unsupported) = lwjgl.to_manifest(Some(&meta_dir)).unwrap();
+    assert_eq!(unsupported.len(), 1);
+    lwjgl.pack.components[0] = serde_json::from_value(serde_json::json!({ "uid": "org.lwjgl3", "version": "3.3.3" })).unwrap();
+    let (_, unsupported) = lwjgl.to_manifest(Some(&meta_dir)).unwrap();
+    assert_eq!(unsupported[0].uid, "org.lwjgl3");
+    assert_eq!(unsupported[0].reason, "pins LWJGL 3.3.3, the vanilla version uses 3.3.1");
+
+    // Forge is installed by `import` with a Java path, and reported as unsupported without one
+    let mut pack: MmcPack = serde_json::from_value(pack).unwrap();
+    pack.components.retain(|component| component.uid == MINECRAFT_UID);
+    pack.components.push(serde_json::from_value(serde_json::json!({ "uid": "net.minecraftforge", "version": "47.3.0" })).unwrap());
+    fs::write(source.join(MMC_PACK), serde_json::to_string(&pack).unwrap()).unwrap();
+    let forge = PrismInstance::load(&source).unwrap();
+    let (manifest, unsupported) = forge.to_manifest(Some(&meta_dir)).unwrap();
+    assert!(manifest.is_none());
+    assert!(unsupported.is_empty());
+    assert_eq!(forge.mod_loader(), Some(ModLoader { kind: ModLoaderKind::Forge, version: "47.3.0".to_string() }));
+
+    let import = super::import(&source, &instances, &mut version_manager, ImportMode::Copy, None, &reporter).await.unwrap();
+    assert!(import.manifest.is_none());
+    assert_eq!(import.unsupported.len(), 1);
+    assert_eq!(import.unsupported[0].reason, "needs a Java path to run its installer");
+    assert_eq!(import.instance.mod_loader, forge.mod_loader());
+
+    let mut neoforge = forge.clone();
+    neoforge.pack.components[1] = serde_json::from_value(serde_json::json!({ "uid": "net.neoforged", "version": "47.1.106" })).unwrap();
+    assert_eq!(neoforge.mod_loader(), Some(ModLoader { kind: ModLoaderKind::NeoForge, version: "1.20.1-47.1.106".to_string() }));
+
+    let _ = fs::remove_dir_all(root);
+  }
+}
@@ -2,7 +2,7 @@ use std::{ collections::HashMap, ops::Deref };
 
 use serde::{ Deserialize, Serialize };
 
-use crate::json::{ manifest::rule::OperatingSystem, Date, Sha1Sum };
+use crate::json::{ manifest::rule::{ Architecture, OperatingSystem }, Date, Sha1Sum };
 
 use super::jvm_platform_string;
 
@@ -25,12 +25,13 @@ impl JreIndex {
     client.get(JRE_INDEX_URL).send().await?.error_for_status()?.json().await
   }
 
-  pub fn find(&self, os: &OperatingSystem, arch: Option<&str>) -> Option<&JreIndexEntry> {
+  /// Finds the runtimes of a platform, falling back to the emulated architecture (e.g. x64 on Apple Silicon)
+  pub fn find(&self, os: &OperatingSystem, arch: &Architecture) -> Option<&JreIndexEntry> {
     if let Some(entry) = self.get(&jvm_platform_string(os, arch)) {
       return Some(entry);
     }
 
-    self.get(&jvm_platform_string(os, None))
+    self.get(&jvm_platform_string(os, &arch.get_emulated(os)?))
   }
 }
 
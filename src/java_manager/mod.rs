@@ -1,4 +1,4 @@
-use std::{ env::consts::ARCH, fs, io, path::{ Path, PathBuf } };
+use std::{ fs, io, path::{ Path, PathBuf } };
 
 use downloadable::RuntimeFileDownloadable;
 use error::InstallRuntimeError;
@@ -10,7 +10,7 @@ use reqwest::Client;
 use sha1::Sha1;
 
 use crate::{
-  json::{ manifest::rule::OperatingSystem, Sha1Sum },
+  json::{ manifest::rule::{ Architecture, OperatingSystem }, Sha1Sum },
   version_manager::downloader::{ download_job::DownloadJob, downloadables::Downloadable, progress::ProgressReporter },
 };
 
@@ -26,7 +26,8 @@ pub struct JavaRuntimeManager {
   pub client: Client,
   pub jre_manifest: Option<JreIndex>,
   pub os: OperatingSystem,
-  pub arch: String,
+  /// Architecture of the runtimes to install, defaults to the one of the launcher
+  pub arch: Architecture,
 }
 
 impl JavaRuntimeManager {
@@ -37,7 +38,7 @@ impl JavaRuntimeManager {
       jre_manifest: None,
 
       os: OperatingSystem::get_current_platform(),
-      arch: ARCH.to_string(),
+      arch: Architecture::current(),
     }
   }
 
@@ -67,7 +68,7 @@ impl JavaRuntimeManager {
 
   pub async fn install_runtime(&self, objects_dir: &Path, component: &str, reporter: &ProgressReporter) -> Result<(), InstallRuntimeError> {
     if let Some(info) = &self.jre_manifest {
-      let entry = info.find(&self.os, Some(&self.arch)).ok_or(InstallRuntimeError::UnsupportedOS)?;
+      let entry = info.find(&self.os, &self.arch).ok_or(InstallRuntimeError::UnsupportedOS)?;
       let runtimes = entry.get(component).ok_or(InstallRuntimeError::RuntimeNotFound { component: component.to_string() })?;
 
       for runtime in runtimes {
@@ -164,13 +165,23 @@ impl JavaRuntimeManager {
     Ok(())
   }
 
+  /// Directory of the runtime, named after its index key. Runtimes installed in the directory
+  /// used by older versions (e.g. `linux-x64`) are still picked up.
   pub fn get_runtime_dir(&self, component: &str) -> PathBuf {
-    let platform = jvm_platform_string(&self.os, Some(&self.arch));
-    self.runtimes_dir.join(component).join(platform)
+    let component_dir = self.runtimes_dir.join(component);
+    let runtime_dir = component_dir.join(jvm_platform_string(&self.os, &self.arch));
+    if !runtime_dir.exists() {
+      if let Some(legacy_dir) = legacy_platform_dir(&self.os, &self.arch).map(|name| component_dir.join(name)) {
+        if legacy_dir.is_dir() {
+          return legacy_dir;
+        }
+      }
+    }
+    runtime_dir
   }
 
   pub fn get_platform_name() -> String {
-    jvm_platform_string(&OperatingSystem::get_current_platform(), Some(ARCH))
+    jvm_platform_string(&OperatingSystem::get_current_platform(), &Architecture::current())
   }
 
   pub fn get_java_executable(&self, component: &str) -> PathBuf {
@@ -188,51 +199,44 @@ impl JavaRuntimeManager {
     }
   }
 
+  #[deprecated(note = "use `java_manager::jvm_platform_string`, which takes an `Architecture`")]
   pub fn jvm_platform_string(os: &OperatingSystem, arch: Option<&str>) -> String {
-    let os = match os {
-      OperatingSystem::Linux => "linux",
-      OperatingSystem::Windows => "windows",
-      OperatingSystem::Osx => "mac-os",
-      _ => {
-        return "gamecore".to_owned();
-      }
-    };
-
-    let arch = match arch {
-      Some("x86_64") => Some("x64"),
-      Some("x64") | Some("x86") | Some("i386") | Some("arm64") | None => arch,
-      _ => None,
-    };
-
-    if let Some(arch) = arch {
-      format!("{os}-{arch}")
-    } else {
-      os.to_owned()
-    }
+    jvm_platform_string(os, &arch.map(Architecture::from_name).unwrap_or(Architecture::Unknown))
   }
 }
 
-pub fn jvm_platform_string(os: &OperatingSystem, arch: Option<&str>) -> String {
+/// Returns the key of the platform in the Java runtime index (`linux`, `linux-i386`, `mac-os-arm64`, `windows-x64`, ...)
+pub fn jvm_platform_string(os: &OperatingSystem, arch: &Architecture) -> String {
+  let platform = match (os, arch) {
+    (OperatingSystem::Linux, Architecture::X64 | Architecture::Unknown) => "linux",
+    (OperatingSystem::Linux, Architecture::X86) => "linux-i386",
+    (OperatingSystem::Linux, Architecture::Arm32) => "linux-arm32",
+    (OperatingSystem::Linux, Architecture::Arm64) => "linux-arm64",
+    (OperatingSystem::Osx, Architecture::Arm64) => "mac-os-arm64",
+    (OperatingSystem::Osx, _) => "mac-os",
+    (OperatingSystem::Windows, Architecture::X86 | Architecture::Arm32) => "windows-x86",
+    (OperatingSystem::Windows, Architecture::Arm64) => "windows-arm64",
+    (OperatingSystem::Windows, _) => "windows-x64",
+    (OperatingSystem::Unknown, _) => "gamecore",
+  };
+  platform.to_string()
+}
+
+/// Directory runtimes were installed in before it followed the index key.
+/// Other architectures used the bare OS name, which held a runtime for the wrong architecture.
+fn legacy_platform_dir(os: &OperatingSystem, arch: &Architecture) -> Option<String> {
   let os = match os {
     OperatingSystem::Linux => "linux",
     OperatingSystem::Windows => "windows",
     OperatingSystem::Osx => "mac-os",
-    _ => {
-      return "gamecore".to_owned();
-    }
+    OperatingSystem::Unknown => return None,
   };
-
   let arch = match arch {
-    Some("x86_64") => Some("x64"),
-    Some("x64") | Some("x86") | Some("i386") | Some("arm64") | None => arch,
-    _ => None,
+    Architecture::X64 => "x64",
+    Architecture::X86 => "x86",
+    _ => return None,
   };
-
-  if let Some(arch) = arch {
-    format!("{os}-{arch}")
-  } else {
-    os.to_owned()
-  }
+  Some(format!("{os}-{arch}"))
 }
 
 #[cfg(test)]
@@ -254,4 +258,33 @@ mod tests {
     let reporter: ProgressReporter = Arc::new(CallbackReporter::new(|_| {}));
     manager.install_runtime(&objects_dir, "jre-legacy", &reporter).await.unwrap();
   }
+
+  #[test]
+  fn test_platform_string() {
+    assert_eq!(jvm_platform_string(&OperatingSystem::Linux, &Architecture::from_name("x86_64")), "linux");
+    assert_eq!(jvm_platform_string(&OperatingSystem::Linux, &Architecture::from_name("aarch64")), "linux-arm64");
+    assert_eq!(jvm_platform_string(&OperatingSystem::Linux, &Architecture::from_name("x86")), "linux-i386");
+    assert_eq!(jvm_platform_string(&OperatingSystem::Osx, &Architecture::Arm64), "mac-os-arm64");
+    assert_eq!(jvm_platform_string(&OperatingSystem::Windows, &Architecture::X86), "windows-x86");
+    #[allow(deprecated)]
+    let deprecated = JavaRuntimeManager::jvm_platform_string(&OperatingSystem::Linux, Some("x86_64"));
+    assert_eq!(deprecated, "linux");
+  }
+
+  #[test]
+  fn test_legacy_runtime_dir() {
+    let runtimes_dir = temp_dir().join(format!("mlc-runtimes-{}", std::process::id()));
+    let mut manager = JavaRuntimeManager::new(&runtimes_dir, &Client::new());
+    manager.os = OperatingSystem::Linux;
+    manager.arch = Architecture::X64;
+    assert_eq!(manager.get_runtime_dir("java-runtime-gamma"), runtimes_dir.join("java-runtime-gamma").join("linux"));
+
+    let legacy_dir = runtimes_dir.join("java-runtime-gamma").join("linux-x64");
+    fs::create_dir_all(&legacy_dir).unwrap();
+    assert_eq!(manager.get_runtime_dir("java-runtime-gamma"), legacy_dir);
+    manager.arch = Architecture::Arm64;
+    assert_eq!(manager.get_runtime_dir("java-runtime-gamma"), runtimes_dir.join("java-runtime-gamma").join("linux-arm64"));
+
+    let _ = fs::remove_dir_all(runtimes_dir);
+  }
 }
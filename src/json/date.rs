@@ -37,7 +37,7 @@ impl<'de> Deserialize<'de> for Date {
     // Insert colon before the last two digits of the offset if missing
     if date.len() > 5 {
       let tz_part = &date[date.len()-5..];
-      if tz_part.chars().nth(3) != Some(':') {
+      if (tz_part.starts_with('+') || tz_part.starts_with('-')) && tz_part[1..].chars().all(|c| c.is_ascii_digit()) {
         // Example: "+0000" -> "+00:00"
         date.insert(date.len() - 2, ':');
       }
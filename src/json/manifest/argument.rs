@@ -2,7 +2,7 @@ use serde::{ Deserialize, Serialize };
 
 use crate::json::EnvironmentFeatures;
 
-use super::rule::{ Rule, RuleAction };
+use super::rule::{ Platform, Rule, RuleAction };
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 #[serde(rename_all = "snake_case")]
@@ -22,8 +22,8 @@ pub enum Argument {
 }
 
 impl Argument {
-  pub fn apply(&self, env_features: &EnvironmentFeatures) -> Option<Vec<&String>> {
-    if self.applies_to_current_environment(env_features) { Some(self.value()) } else { None }
+  pub fn apply(&self, env_features: &EnvironmentFeatures, platform: &Platform) -> Option<Vec<&String>> {
+    if self.applies_to_environment(env_features, platform) { Some(self.value()) } else { None }
   }
 
   pub fn value(&self) -> Vec<&String> {
@@ -33,7 +33,7 @@ impl Argument {
     }
   }
 
-  pub fn applies_to_current_environment(&self, env_features: &EnvironmentFeatures) -> bool {
+  pub fn applies_to_environment(&self, env_features: &EnvironmentFeatures, platform: &Platform) -> bool {
     if let Argument::Object { rules, .. } = self {
       // TODO: needed?
       if rules.is_empty() {
@@ -42,7 +42,7 @@ impl Argument {
 
       let mut action = RuleAction::Disallow;
       for rule in rules {
-        if let Some(applied_action) = rule.get_applied_action(env_features) {
+        if let Some(applied_action) = rule.get_applied_action(env_features, platform) {
           action = applied_action;
         }
       }
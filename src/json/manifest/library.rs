@@ -1,10 +1,10 @@
-use std::{ collections::HashMap, env::consts::ARCH, path::Path };
+use std::{ collections::HashMap, path::Path };
 
 use serde::{ Deserialize, Serialize };
 
 use crate::json::EnvironmentFeatures;
 
-use super::{ artifact::Artifact, rule::{ OperatingSystem, Rule, RuleAction }, DownloadInfo };
+use super::{ artifact::Artifact, rule::{ Architecture, OperatingSystem, Platform, Rule, RuleAction }, DownloadInfo };
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -23,14 +23,17 @@ pub struct Library {
 }
 
 impl Library {
-  pub fn applies_to_current_environment(&self, env_features: &EnvironmentFeatures) -> bool {
+  pub fn applies_to_environment(&self, env_features: &EnvironmentFeatures, platform: &Platform) -> bool {
+    if !self.supports_arch(&platform.arch) {
+      return false;
+    }
     if self.rules.is_empty() {
       return true;
     }
 
     let mut action = RuleAction::Disallow;
     for rule in &self.rules {
-      if let Some(applied_action) = rule.get_applied_action(env_features) {
+      if let Some(applied_action) = rule.get_applied_action(env_features, platform) {
         action = applied_action;
       }
     }
@@ -46,22 +49,38 @@ impl Library {
     new_artifact.get_path_string()
   }
 
-  pub fn get_artifact_classifier(&self, os: &OperatingSystem) -> Option<Option<String>> {
+  /// Architecture of a LWJGL 3 style classpath natives jar (`natives-linux-arm64`), `None` for other libraries.
+  /// Natives without an architecture suffix are built for x64.
+  pub fn get_natives_arch(&self) -> Option<Architecture> {
+    let natives = self.name.classifier.as_ref()?.strip_prefix("natives-")?;
+    let arch = natives.split_once('-').map(|(_, arch)| Architecture::from_name(arch)).unwrap_or(Architecture::X64);
+    Some(arch)
+  }
+
+  /// Returns whether the library can be used on `arch`. Manifests list the classpath natives of every
+  /// architecture for an OS, only the ones matching the target must be loaded.
+  pub fn supports_arch(&self, arch: &Architecture) -> bool {
+    match self.get_natives_arch() {
+      Some(Architecture::Unknown) | None => true,
+      Some(natives_arch) => &natives_arch == arch,
+    }
+  }
+
+  pub fn get_artifact_classifier(&self, os: &OperatingSystem, arch: &Architecture) -> Option<Option<String>> {
     if self.natives.is_empty() {
       return Some(None);
     }
 
     if let Some(classifier) = self.natives.get(os) {
-      let arch = if ARCH == "x86" { "32" } else { "64" };
-      let classifier = classifier.replace("${arch}", arch);
+      let classifier = classifier.replace("${arch}", arch.get_bits());
       return Some(Some(classifier));
     }
 
     None
   }
 
-  pub fn get_download_info(&self, os: &OperatingSystem) -> Option<DownloadInfo> {
-    let classifier = self.get_artifact_classifier(os)?;
+  pub fn get_download_info(&self, os: &OperatingSystem, arch: &Architecture) -> Option<DownloadInfo> {
+    let classifier = self.get_artifact_classifier(os, arch)?;
 
     if let Some(downloads) = &self.downloads {
       downloads.get_download_info(classifier)
@@ -100,3 +119,29 @@ impl LibraryDownloadInfo {
     if let Some(classifier) = classifier { self.classifiers.get(&classifier).cloned() } else { self.artifact.clone() }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn library(value: serde_json::Value) -> Library {
+    serde_json::from_value(value).unwrap()
+  }
+
+  #[test]
+  fn test_library_arch() {
+    let arm64 = library(serde_json::json!({ "name": "org.lwjgl:lwjgl:3.3.3:natives-linux-arm64" }));
+    let x64 = library(serde_json::json!({ "name": "org.lwjgl:lwjgl:3.3.3:natives-linux" }));
+    assert!(arm64.supports_arch(&Architecture::Arm64) && !arm64.supports_arch(&Architecture::X64));
+    assert!(x64.supports_arch(&Architecture::X64) && !x64.supports_arch(&Architecture::Arm64));
+
+    let legacy = library(serde_json::json!({
+      "name": "tv.twitch:twitch-platform:5.16",
+      "natives": { "windows": "natives-windows-${arch}" }
+    }));
+    assert!(legacy.supports_arch(&Architecture::X86));
+    assert_eq!(legacy.get_artifact_classifier(&OperatingSystem::Windows, &Architecture::X86), Some(Some("natives-windows-32".into())));
+    assert_eq!(legacy.get_artifact_classifier(&OperatingSystem::Windows, &Architecture::Arm64), Some(Some("natives-windows-64".into())));
+    assert_eq!(legacy.get_artifact_classifier(&OperatingSystem::Linux, &Architecture::X64), None);
+  }
+}
@@ -6,7 +6,7 @@ use download::{ DownloadInfo, DownloadType };
 use java::JavaVersionInfo;
 use library::Library;
 use logging::LoggingEntry;
-use rule::{ OperatingSystem, Rule, RuleAction };
+use rule::{ Platform, Rule, RuleAction };
 use serde::{ Deserialize, Serialize };
 
 use super::{ Date, EnvironmentFeatures, MCVersion, ReleaseType, VersionInfo };
@@ -27,7 +27,7 @@ pub struct VersionManifest {
   pub arguments: HashMap<ArgumentType, Vec<Argument>>,
   #[serde(default, skip_serializing_if = "Option::is_none")]
   pub minecraft_arguments: Option<String>,
-  #[serde(default, skip_serializing_if = "Option::is_none", rename = "libraries")]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
   pub asset_index: Option<AssetIndexInfo>,
   #[serde(default, skip_serializing_if = "Option::is_none")]
   pub assets: Option<String>,
@@ -60,23 +60,19 @@ pub struct VersionManifest {
 }
 
 impl VersionManifest {
-  pub fn get_relevant_libraries(&self, env_features: &EnvironmentFeatures) -> Vec<&Library> {
+  pub fn get_relevant_libraries(&self, env_features: &EnvironmentFeatures, platform: &Platform) -> Vec<&Library> {
     self.libraries
       .iter()
-      .filter(|lib| lib.applies_to_current_environment(env_features))
+      .filter(|lib| lib.applies_to_environment(env_features, platform))
       .collect()
   }
 
-  pub fn get_required_files(&self, os: &OperatingSystem, env_features: &EnvironmentFeatures) -> HashSet<String> {
+  pub fn get_required_files(&self, platform: &Platform, env_features: &EnvironmentFeatures) -> HashSet<String> {
     let mut set = HashSet::new();
-    let libraries = self.get_relevant_libraries(env_features);
+    let libraries = self.get_relevant_libraries(env_features, platform);
     for library in libraries {
-      if !library.natives.is_empty() {
-        if let Some(native) = library.natives.get(os) {
-          set.insert(format!("libraries/{}", library.get_artifact_path(Some(native.clone()))));
-        }
-      } else {
-        set.insert(format!("libraries/{}", library.get_artifact_path(None)));
+      if let Some(classifier) = library.get_artifact_classifier(&platform.os, &platform.arch) {
+        set.insert(format!("libraries/{}", library.get_artifact_path(classifier)));
       }
     }
     set
@@ -94,14 +90,14 @@ impl VersionManifest {
     self.downloads.get(&download_type)
   }
 
-  pub fn applies_to_current_environment(&self, env_features: &EnvironmentFeatures) -> bool {
+  pub fn applies_to_environment(&self, env_features: &EnvironmentFeatures, platform: &Platform) -> bool {
     if self.compatibility_rules.is_empty() {
       return true;
     }
 
     let mut action = RuleAction::Disallow;
     for rule in &self.compatibility_rules {
-      if let Some(applied_action) = rule.get_applied_action(env_features) {
+      if let Some(applied_action) = rule.get_applied_action(env_features, platform) {
         action = applied_action;
       }
     }
@@ -109,9 +105,9 @@ impl VersionManifest {
     action == RuleAction::Allow
   }
 
-  pub fn get_classpath(&self, _os: &OperatingSystem, mc_dir: &Path, env_features: &EnvironmentFeatures) -> Vec<PathBuf> {
+  pub fn get_classpath(&self, platform: &Platform, mc_dir: &Path, env_features: &EnvironmentFeatures) -> Vec<PathBuf> {
     let mut vec = vec![];
-    let libraries = self.get_relevant_libraries(env_features);
+    let libraries = self.get_relevant_libraries(env_features, platform);
     for library in libraries {
       if library.natives.is_empty() {
         vec.push(mc_dir.join("libraries").join(library.get_artifact_path(None).replace('/', MAIN_SEPARATOR_STR)));
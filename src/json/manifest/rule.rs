@@ -1,4 +1,4 @@
-use std::{ collections::HashMap, env::consts::{ OS, ARCH }, fmt::Debug };
+use std::{ collections::HashMap, env::consts::{ OS, ARCH }, fmt::{ Debug, Display } };
 
 use os_info::Version;
 use regex::Regex;
@@ -20,25 +20,25 @@ pub struct Rule {
 }
 
 impl Rule {
-  /// Retrieves the action to be applied based on the current environment features.
+  /// Retrieves the action to be applied based on the environment features and target platform.
   ///
-  /// This function checks if the current environment meets the necessary conditions
+  /// This function checks if the environment meets the necessary conditions
   /// defined in `self`. It considers operating system restrictions and other feature
   /// restrictions specified in `self`.
   ///
   /// # Arguments
   ///
   /// * `env_features` - A struct containing the features of the current environment.
+  /// * `platform` - The platform the version is resolved for.
   ///
   /// # Returns
   ///
   /// Returns `Some(RuleAction)` if the current environment meets all the conditions;
   /// otherwise, it returns `None` if any condition is not met.
-
-  pub fn get_applied_action(&self, env_features: &EnvironmentFeatures) -> Option<RuleAction> {
+  pub fn get_applied_action(&self, env_features: &EnvironmentFeatures, platform: &Platform) -> Option<RuleAction> {
     // If there's some operating system restriction, check if it's met
     if let Some(os) = &self.os {
-      if !os.is_current_operating_system() {
+      if !os.matches(platform) {
         return None;
       }
     }
@@ -86,23 +86,27 @@ pub struct OsRestriction {
 
 impl OsRestriction {
   pub fn is_current_operating_system(&self) -> bool {
+    self.matches(&Platform::current())
+  }
+
+  pub fn matches(&self, platform: &Platform) -> bool {
     let OsRestriction { name, arch, version } = &self;
 
     if let Some(name) = name {
-      if &OperatingSystem::get_current_platform() != name {
+      if &platform.os != name {
         return false;
       }
     }
 
     if let Some(arch) = arch {
-      if &get_arch() != arch {
+      if !Self::matches_arch(arch, &platform.arch) {
         return false;
       }
     }
 
     if let Some(version) = version {
       if let Ok(regex) = Regex::new(version) {
-        if !regex.is_match(&get_os_version()) {
+        if !regex.is_match(&platform.os_version) {
           return false;
         }
       }
@@ -110,6 +114,73 @@ impl OsRestriction {
 
     true
   }
+
+  /// Returns whether the `arch` of a restriction matches the target architecture, accepting aliases like `amd64` or `aarch64`
+  pub fn matches_arch(arch: &str, target: &Architecture) -> bool {
+    let arch = Architecture::from_name(arch);
+    arch != Architecture::Unknown && &arch == target
+  }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Architecture {
+  X86,
+  X64,
+  Arm32,
+  Arm64,
+  Unknown,
+}
+
+impl Architecture {
+  /// Architecture of the running launcher
+  pub fn current() -> Self {
+    Self::from_name(ARCH)
+  }
+
+  /// Parses Rust (`aarch64`), Mojang (`x86`) and Java (`amd64`) architecture names
+  pub fn from_name(name: &str) -> Self {
+    match name.to_lowercase().as_str() {
+      "x86" | "i386" | "i686" => Architecture::X86,
+      "x64" | "x86_64" | "amd64" => Architecture::X64,
+      "arm" | "arm32" | "armv7" | "armhf" => Architecture::Arm32,
+      "arm64" | "aarch64" => Architecture::Arm64,
+      _ => Architecture::Unknown,
+    }
+  }
+
+  pub fn get_name(&self) -> &'static str {
+    match self {
+      Architecture::X86 => "x86",
+      Architecture::X64 => "x64",
+      Architecture::Arm32 => "arm32",
+      Architecture::Arm64 => "arm64",
+      Architecture::Unknown => "unknown",
+    }
+  }
+
+  /// Unknown architectures (riscv64, ppc64le, loongarch64, ...) are assumed to be 64-bit
+  pub fn is_64bit(&self) -> bool {
+    !matches!(self, Architecture::X86 | Architecture::Arm32)
+  }
+
+  /// Value of `${arch}` in native classifiers
+  pub fn get_bits(&self) -> &'static str {
+    if self.is_64bit() { "64" } else { "32" }
+  }
+
+  /// Architecture the OS can run through emulation when no native build exists (Rosetta 2, Windows on ARM)
+  pub fn get_emulated(&self, os: &OperatingSystem) -> Option<Architecture> {
+    match (os, self) {
+      (OperatingSystem::Osx | OperatingSystem::Windows, Architecture::Arm64) => Some(Architecture::X64),
+      _ => None,
+    }
+  }
+}
+
+impl Display for Architecture {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.get_name())
+  }
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize, Eq, Clone, Hash)]
@@ -164,13 +235,38 @@ impl OperatingSystem {
   }
 }
 
+/// The system a version is resolved for, rules, natives and the classpath depend on it
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Platform {
+  pub os: OperatingSystem,
+  pub arch: Architecture,
+  /// Version matched by the `os.version` regex of rules (e.g. `10.0` on Windows 10)
+  pub os_version: String,
+}
+
+impl Platform {
+  pub fn new(os: OperatingSystem, arch: Architecture, os_version: impl AsRef<str>) -> Self {
+    Self { os, arch, os_version: os_version.as_ref().to_string() }
+  }
+
+  /// The system the launcher is running on
+  pub fn current() -> Self {
+    Self::new(OperatingSystem::get_current_platform(), Architecture::current(), get_os_version())
+  }
+
+  pub fn classpath_separator(&self) -> &'static str {
+    if self.os == OperatingSystem::Windows { ";" } else { ":" }
+  }
+}
+
+impl Default for Platform {
+  fn default() -> Self {
+    Self::current()
+  }
+}
+
 pub fn get_arch() -> String {
-  let arch = match ARCH {
-    "x86_64" => "x64",
-    "x86" => "x86",
-    s => s,
-  };
-  arch.to_string()
+  Architecture::current().to_string()
 }
 
 pub fn get_os_version() -> String {
@@ -180,3 +276,29 @@ pub fn get_os_version() -> String {
     _ => "unknown".to_string(),
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_rule_platform() {
+    let rule: Rule = serde_json::from_value(
+      serde_json::json!({ "action": "allow", "os": { "name": "windows", "version": "^10\\." } })
+    ).unwrap();
+    let env_features = EnvironmentFeatures::default();
+
+    let windows_10 = Platform::new(OperatingSystem::Windows, Architecture::X64, "10.0.19045");
+    let windows_7 = Platform::new(OperatingSystem::Windows, Architecture::X64, "6.1.7601");
+    let linux = Platform::new(OperatingSystem::Linux, Architecture::Arm64, "6.8.0");
+    assert_eq!(rule.get_applied_action(&env_features, &windows_10), Some(RuleAction::Allow));
+    assert_eq!(rule.get_applied_action(&env_features, &windows_7), None);
+    assert_eq!(rule.get_applied_action(&env_features, &linux), None);
+
+    let x86: Rule = serde_json::from_value(serde_json::json!({ "action": "disallow", "os": { "arch": "x86" } })).unwrap();
+    assert_eq!(x86.get_applied_action(&env_features, &linux), None);
+    assert_eq!(x86.get_applied_action(&env_features, &Platform::new(OperatingSystem::Linux, Architecture::X86, "")), Some(RuleAction::Disallow));
+    assert!(Architecture::from_name("riscv64").is_64bit());
+    assert_eq!(Architecture::Arm32.get_bits(), "32");
+  }
+}
@@ -6,6 +6,10 @@ pub mod java_manager;
 pub mod version_manager;
 #[cfg(feature = "bootstrap")]
 pub mod bootstrap;
+#[cfg(feature = "instances")]
+pub mod instances;
+#[cfg(feature = "mod_loaders")]
+pub mod mod_loaders;
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,33 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+use crate::version_manager::{ downloader, error::{ InstallVersionError, MavenError, ResolveManifestError } };
+
+#[derive(Debug, Error)]
+pub enum ModLoaderError {
+  #[error("Loader version not found: {0}")] VersionNotFound(String),
+  #[error("Invalid installer: {0}")] InvalidInstaller(String),
+  #[error("Processor {processor} failed with exit code {code:?}: {stderr}")] ProcessorFailed {
+    processor: String,
+    code: Option<i32>,
+    stderr: String,
+  },
+  #[error("Processor output {path} doesn't match its SHA-1 {expected}")] OutputMismatch {
+    path: PathBuf,
+    expected: String,
+  },
+  #[error("Library {library} doesn't match the checksums of the installer")] LibraryMismatch {
+    library: String,
+    path: PathBuf,
+  },
+  #[error("Library {0} required by the installer is missing")] MissingLibrary(String),
+  #[error("Failed to fetch loader metadata: {0}")] Fetch(#[from] reqwest::Error),
+  #[error("Failed to parse loader metadata: {0}")] Parse(#[from] serde_json::Error),
+  #[error("Failed to download loader files: {0}")] Download(#[from] downloader::error::Error),
+  #[error("Failed to read installer: {0}")] Zip(#[from] zip::result::ZipError),
+  #[error(transparent)] Maven(#[from] MavenError),
+  #[error(transparent)] InstallVersion(#[from] InstallVersionError),
+  #[error(transparent)] ResolveVersion(#[from] ResolveManifestError),
+  #[error(transparent)] IO(#[from] std::io::Error),
+}
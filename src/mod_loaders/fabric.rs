@@ -0,0 +1,162 @@
+use log::info;
+use reqwest::Client;
+use serde::{ de::DeserializeOwned, Deserialize, Serialize };
+
+use crate::{ json::{ manifest::VersionManifest, MCVersion }, version_manager::VersionManager };
+
+use super::{ error::ModLoaderError, fetch_meta };
+
+pub const FABRIC_META_URL: &str = "https://meta.fabricmc.net/v2";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameVersion {
+  pub version: String,
+  pub stable: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoaderVersion {
+  pub version: String,
+  /// Maven coordinates of the loader, e.g. `net.fabricmc:fabric-loader:0.15.11`
+  pub maven: String,
+  #[serde(default)]
+  pub stable: bool,
+}
+
+/// An entry of `/versions/loader/<game>`, a loader compatible with the game version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(super) struct CompatibleLoader {
+  pub(super) loader: LoaderVersion,
+}
+
+/// Client of the Fabric meta API, installs Fabric profiles as versions inheriting from vanilla
+#[derive(Debug, Clone)]
+pub struct FabricInstaller {
+  /// Base URL of the meta API, without a trailing slash
+  pub meta_url: String,
+  pub client: Client,
+}
+
+impl FabricInstaller {
+  pub fn new(client: &Client) -> Self {
+    Self::with_meta_url(client, FABRIC_META_URL)
+  }
+
+  /// Creates an installer using another meta server, e.g. a mirror or a compatible API
+  pub fn with_meta_url(client: &Client, meta_url: &str) -> Self {
+    Self { meta_url: meta_url.trim_end_matches('/').to_string(), client: client.clone() }
+  }
+
+  async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, ModLoaderError> {
+    fetch_meta(&self.client, &self.meta_url, path).await
+  }
+
+  /// Game versions supported by Fabric, newest first
+  pub async fn game_versions(&self) -> Result<Vec<GameVersion>, ModLoaderError> {
+    self.get("versions/game").await
+  }
+
+  /// Every loader version, newest first
+  pub async fn loader_versions(&self) -> Result<Vec<LoaderVersion>, ModLoaderError> {
+    self.get("versions/loader").await
+  }
+
+  /// Loader versions compatible with a game version, newest first
+  pub async fn loader_versions_for(&self, game_version: &MCVersion) -> Result<Vec<LoaderVersion>, ModLoaderError> {
+    let loaders: Vec<CompatibleLoader> = self.get(&format!("versions/loader/{game_version}")).await?;
+    Ok(loaders.into_iter().map(|entry| entry.loader).collect())
+  }
+
+  /// Latest stable loader compatible with a game version
+  pub async fn latest_loader(&self, game_version: &MCVersion) -> Result<LoaderVersion, ModLoaderError> {
+    self
+      .loader_versions_for(game_version).await?
+      .into_iter()
+      .find(|loader| loader.stable)
+      .ok_or_else(|| ModLoaderError::VersionNotFound(format!("no stable Fabric loader for {game_version}")))
+  }
+
+  /// Fetches the launcher profile of a loader version, a manifest inheriting from `game_version`
+  pub async fn fetch_profile(&self, game_version: &MCVersion, loader_version: &str) -> Result<VersionManifest, ModLoaderError> {
+    self.get(&profile_path(game_version, loader_version)).await
+  }
+
+  /// Installs the profile to `versions/<id>/` and resolves it, installing the vanilla version if needed.
+  ///
+  /// The libraries are downloaded from their Maven `url` with the rest of the version.
+  pub async fn install(
+    &self,
+    version_manager: &mut VersionManager,
+    game_version: &MCVersion,
+    loader_version: &str
+  ) -> Result<VersionManifest, ModLoaderError> {
+    info!("Installing Fabric {loader_version} for {game_version}");
+    let profile = self.fetch_profile(game_version, loader_version).await?;
+    install_profile(version_manager, &profile).await
+  }
+}
+
+/// Writes a loader profile to `versions/<id>/` and resolves it against the version it inherits from
+pub(super) async fn install_profile(version_manager: &mut VersionManager, profile: &VersionManifest) -> Result<VersionManifest, ModLoaderError> {
+  version_manager.install_local_version(profile)?;
+  Ok(version_manager.resolve_local_version(&profile.id, false, true).await?)
+}
+
+pub(super) fn profile_path(game_version: &MCVersion, loader_version: &str) -> String {
+  format!("versions/loader/{game_version}/{loader_version}/profile/json")
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{ env::temp_dir, fs };
+
+  use crate::json::EnvironmentFeatures;
+
+  use super::*;
+
+  #[tokio::test]
+  async fn test_fabric_profile() {
+    let installer = FabricInstaller::with_meta_url(&Client::new(), "https://meta.example.org/v2/");
+    assert_eq!(installer.meta_url, "https://meta.example.org/v2");
+    assert_eq!(profile_path(&MCVersion::new("1.20.1"), "0.15.11"), "versions/loader/1.20.1/0.15.11/profile/json");
+
+    let profile: VersionManifest = serde_json::from_value(
+      serde_json::json!({
+        "id": "fabric-loader-0.15.11-1.20.1",
+        "inheritsFrom": "1.20.1",
+        "releaseTime": "2024-05-04T12:00:00+0000",
+        "time": "2024-05-04T12:00:00+0000",
+        "type": "release",
+        "mainClass": "net.fabricmc.loader.impl.launch.knot.KnotClient",
+        "arguments": { "game": [], "jvm": ["-DFabricMcEmu= net.minecraft.client.main.Main "] },
+        "libraries": [
+          { "name": "net.fabricmc:intermediary:1.20.1", "url": "https://maven.fabricmc.net/" },
+          {
+            "name": "net.fabricmc:fabric-loader:0.15.11",
+            "url": "https://maven.fabricmc.net/",
+            "sha1": "f60cb3bd4d3c8ec5ee64b4e4f8d48dfe4f39a7d7",
+            "size": 1306082
+          }
+        ]
+      })
+    ).unwrap();
+    assert_eq!(profile.inherits_from, Some(MCVersion::new("1.20.1")));
+    assert_eq!(profile.libraries[1].get_artifact_path(None), "net/fabricmc/fabric-loader/0.15.11/fabric-loader-0.15.11.jar");
+
+    let game_dir = temp_dir().join(format!("mlc-fabric-{}", std::process::id()));
+    let mut version_manager = VersionManager::new(&game_dir, &EnvironmentFeatures::default(), None);
+    let vanilla: VersionManifest = serde_json::from_str(&fs::read_to_string("tests/fixtures/inheritance/1.20.1.json").unwrap()).unwrap();
+    version_manager.install_local_version(&vanilla).unwrap();
+
+    let resolved = install_profile(&mut version_manager, &profile).await.unwrap();
+    assert_eq!(resolved.id, profile.id);
+    assert_eq!(resolved.inherits_from, None);
+    assert_eq!(resolved.main_class.as_deref(), Some("net.fabricmc.loader.impl.launch.knot.KnotClient"));
+    assert_eq!(resolved.assets, vanilla.assets);
+    let names: Vec<_> = resolved.libraries.iter().map(|library| library.name.to_string()).collect();
+    assert_eq!(names[..2], ["net.fabricmc:intermediary:1.20.1", "net.fabricmc:fabric-loader:0.15.11"]);
+    assert_eq!(names.len(), profile.libraries.len() + vanilla.libraries.len());
+    assert!(game_dir.join("versions").join("fabric-loader-0.15.11-1.20.1").join("fabric-loader-0.15.11-1.20.1.json").is_file());
+    let _ = fs::remove_dir_all(game_dir);
+  }
+}
@@ -0,0 +1,71 @@
+use std::path::{ Path, PathBuf };
+
+use reqwest::Client;
+
+use crate::{
+  json::{ manifest::VersionManifest, MCVersion },
+  version_manager::{ downloader::progress::ProgressReporter, VersionManager },
+};
+
+use super::{ error::ModLoaderError, installer::{ download_installer, InstallerJar }, legacy_forge::{ is_legacy_installer, LegacyInstallerJar } };
+
+pub const FORGE_MAVEN_URL: &str = "https://maven.minecraftforge.net/";
+
+/// Installs Forge, running the installer processors for 1.13+ and converting the profile of older installers
+#[derive(Debug, Clone)]
+pub struct ForgeInstaller {
+  /// Maven repository hosting the installers, with a trailing slash
+  pub maven_url: String,
+  pub client: Client,
+}
+
+impl ForgeInstaller {
+  pub fn new(client: &Client) -> Self {
+    Self::with_maven_url(client, FORGE_MAVEN_URL)
+  }
+
+  pub fn with_maven_url(client: &Client, maven_url: &str) -> Self {
+    Self { maven_url: format!("{}/", maven_url.trim_end_matches('/')), client: client.clone() }
+  }
+
+  /// Maven path of the installer, relative to the repository.
+  ///
+  /// Some old versions have a branch suffix in `forge_version`, e.g. `10.13.4.1614-1.7.10`.
+  pub fn installer_path(minecraft_version: &MCVersion, forge_version: &str) -> String {
+    let version = format!("{minecraft_version}-{forge_version}");
+    format!("net/minecraftforge/forge/{version}/forge-{version}-installer.jar")
+  }
+
+  /// Downloads the installer into `libraries/`, checked against the `.sha1` published next to it
+  pub async fn download_installer(
+    &self,
+    libraries_dir: &Path,
+    minecraft_version: &MCVersion,
+    forge_version: &str,
+    reporter: &ProgressReporter
+  ) -> Result<PathBuf, ModLoaderError> {
+    let path = Self::installer_path(minecraft_version, forge_version);
+    let target = libraries_dir.join(&path);
+    download_installer(&self.client, &format!("{}{path}", self.maven_url), &target, reporter).await?;
+    Ok(target)
+  }
+
+  /// Downloads and runs the installer, returning the resolved Forge version.
+  /// `java_path` runs the processors, legacy installers don't need it.
+  pub async fn install(
+    &self,
+    version_manager: &mut VersionManager,
+    minecraft_version: &MCVersion,
+    forge_version: &str,
+    java_path: &Path,
+    reporter: &ProgressReporter
+  ) -> Result<VersionManifest, ModLoaderError> {
+    let libraries_dir = version_manager.game_dir.join("libraries");
+    let installer = self.download_installer(&libraries_dir, minecraft_version, forge_version, reporter).await?;
+    if is_legacy_installer(&installer)? {
+      LegacyInstallerJar::open(&installer)?.install(version_manager, reporter).await
+    } else {
+      InstallerJar::open(&installer)?.install(version_manager, java_path, reporter).await
+    }
+  }
+}
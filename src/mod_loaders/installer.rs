@@ -0,0 +1,481 @@
+use std::{ collections::HashMap, env::temp_dir, fs::{ self, File }, io::{ self, Read }, path::{ Path, PathBuf }, process::Command };
+
+use log::{ debug, info };
+use reqwest::Client;
+use serde::{ Deserialize, Serialize };
+use zip::ZipArchive;
+
+use crate::{
+  json::{ manifest::{ artifact::Artifact, library::Library, rule::Platform, VersionManifest }, MCVersion, Sha1Sum },
+  version_manager::{
+    downloader::{
+      download_job::DownloadJob,
+      downloadables::ChecksummedDownloadable,
+      progress::ProgressReporter,
+      utils::{ create_lib_downloadable, get_jar_downloadable },
+    },
+    VersionManager,
+  },
+};
+
+use super::error::ModLoaderError;
+
+pub const INSTALL_PROFILE: &str = "install_profile.json";
+
+/// The `install_profile.json` of a Forge 1.13+ (or NeoForge) installer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallProfile {
+  #[serde(default)]
+  pub spec: u32,
+  /// Id of the installed version
+  pub version: MCVersion,
+  pub minecraft: MCVersion,
+  /// Path of the version manifest inside the installer
+  #[serde(default = "default_version_json")]
+  pub json: String,
+  /// Values of the `{KEY}` processor arguments, per side
+  #[serde(default)]
+  pub data: HashMap<String, SidedData>,
+  #[serde(default)]
+  pub processors: Vec<Processor>,
+  /// Libraries needed by the processors
+  #[serde(default)]
+  pub libraries: Vec<Library>,
+}
+
+fn default_version_json() -> String {
+  "/version.json".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SidedData {
+  pub client: String,
+  #[serde(default)]
+  pub server: String,
+}
+
+/// A program run by the installer to generate files, e.g. the patched client jar
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Processor {
+  /// Sides the processor runs on, every side if empty
+  #[serde(default)]
+  pub sides: Vec<String>,
+  pub jar: Artifact,
+  #[serde(default)]
+  pub classpath: Vec<Artifact>,
+  #[serde(default)]
+  pub args: Vec<String>,
+  /// Generated files and their SHA-1, both substituted like the arguments
+  #[serde(default)]
+  pub outputs: HashMap<String, String>,
+}
+
+impl Processor {
+  pub fn runs_on_client(&self) -> bool {
+    self.sides.is_empty() || self.sides.iter().any(|side| side == "client")
+  }
+}
+
+/// An opened Forge-style installer jar
+pub struct InstallerJar {
+  pub path: PathBuf,
+  pub profile: InstallProfile,
+  pub version: VersionManifest,
+  archive: ZipArchive<File>,
+}
+
+impl InstallerJar {
+  pub fn open(path: &Path) -> Result<Self, ModLoaderError> {
+    let mut archive = ZipArchive::new(File::open(path)?)?;
+    let profile: InstallProfile = serde_json::from_slice(&read_entry(&mut archive, INSTALL_PROFILE)?)?;
+    let version = serde_json::from_slice(&read_entry(&mut archive, profile.json.trim_start_matches('/'))?)?;
+    Ok(Self { path: path.to_path_buf(), profile, version, archive })
+  }
+
+  /// Installs the version into the `VersionManager` directory and returns it resolved:
+  ///
+  /// 1. Extracts the libraries embedded in the installer (`maven/`) into `libraries/`
+  /// 2. Downloads the vanilla client jar and the processor libraries
+  /// 3. Runs the client processors with `java_path`, skipping those whose outputs are already valid
+  /// 4. Writes the version manifest to `versions/<id>/`
+  pub async fn install(
+    mut self,
+    version_manager: &mut VersionManager,
+    java_path: &Path,
+    reporter: &ProgressReporter
+  ) -> Result<VersionManifest, ModLoaderError> {
+    info!("Installing {} from {}", self.profile.version, self.path.display());
+    let libraries_dir = version_manager.game_dir.join("libraries");
+    self.extract_maven(&libraries_dir)?;
+
+    let vanilla = version_manager.resolve_local_version(&self.profile.minecraft, false, false).await?;
+    let platform = &version_manager.platform;
+    let downloadables = self.profile.libraries
+      .iter()
+      .chain(&self.version.libraries)
+      .filter_map(|library| create_lib_downloadable(library, &version_manager.game_dir, platform))
+      .chain([get_jar_downloadable(&version_manager.game_dir, &vanilla)])
+      .collect();
+    DownloadJob::new("Loader libraries")
+      .with_client(version_manager.client.clone())
+      .with_progress_reporter(reporter)
+      .add_downloadables(downloadables)
+      .start().await?;
+
+    let minecraft_jar = version_manager.game_dir.join("versions").join(vanilla.id.to_string()).join(format!("{}.jar", vanilla.id));
+    let work_dir = temp_dir().join(format!("mlc-installer-{}-{}", self.profile.version, std::process::id()));
+    let result = self.run_processors(&version_manager.game_dir, &minecraft_jar, java_path, &work_dir);
+    let _ = fs::remove_dir_all(&work_dir);
+    result?;
+
+    version_manager.install_local_version(&self.version)?;
+    Ok(version_manager.resolve_local_version(&self.version.id, false, true).await?)
+  }
+
+  fn extract_maven(&mut self, libraries_dir: &Path) -> Result<(), ModLoaderError> {
+    for i in 0..self.archive.len() {
+      let mut entry = self.archive.by_index(i)?;
+      let Some(path) = entry.enclosed_name() else {
+        continue;
+      };
+      let Ok(relative) = path.strip_prefix("maven") else {
+        continue;
+      };
+      let target = libraries_dir.join(relative);
+      if entry.is_dir() || target.is_file() {
+        continue;
+      }
+      debug!("Extracting {}", relative.display());
+      fs::create_dir_all(target.parent().unwrap_or(libraries_dir))?;
+      io::copy(&mut entry, &mut File::create(target)?)?;
+    }
+    Ok(())
+  }
+
+  fn run_processors(&mut self, game_dir: &Path, minecraft_jar: &Path, java_path: &Path, work_dir: &Path) -> Result<(), ModLoaderError> {
+    let libraries_dir = game_dir.join("libraries");
+    let mut data = HashMap::from([
+      ("SIDE".to_string(), "client".to_string()),
+      ("MINECRAFT_JAR".to_string(), path_string(minecraft_jar)),
+      ("MINECRAFT_VERSION".to_string(), self.profile.minecraft.to_string()),
+      ("ROOT".to_string(), path_string(game_dir)),
+      ("INSTALLER".to_string(), path_string(&self.path)),
+      ("LIBRARY_DIR".to_string(), path_string(&libraries_dir)),
+    ]);
+    for (key, value) in self.profile.data.clone() {
+      let value = match value.client.strip_prefix('/') {
+        // A file inside the installer
+        Some(entry_name) => {
+          let target = work_dir.join(entry_name);
+          fs::create_dir_all(target.parent().unwrap_or(work_dir))?;
+          fs::write(&target, read_entry(&mut self.archive, entry_name)?)?;
+          path_string(&target)
+        }
+        None => substitute_argument(&value.client, &HashMap::new(), &libraries_dir)?,
+      };
+      data.insert(key, value);
+    }
+
+    let separator = Platform::current().classpath_separator();
+    for processor in self.profile.processors.iter().filter(|processor| processor.runs_on_client()) {
+      let outputs = processor.outputs
+        .iter()
+        .map(|(path, sha1)| Ok((substitute_argument(path, &data, &libraries_dir)?, substitute_argument(sha1, &data, &libraries_dir)?)))
+        .collect::<Result<Vec<_>, ModLoaderError>>()?;
+      if !outputs.is_empty() && outputs.iter().all(|(path, sha1)| output_matches(Path::new(path), sha1)) {
+        info!("Skipping processor {}, its outputs are up to date", processor.jar);
+        continue;
+      }
+
+      let jar = processor.jar.get_local_path(&libraries_dir);
+      let main_class = read_main_class(&jar)?;
+      let classpath = [&processor.jar]
+        .into_iter()
+        .chain(&processor.classpath)
+        .map(|artifact| path_string(&artifact.get_local_path(&libraries_dir)))
+        .collect::<Vec<_>>()
+        .join(separator);
+      let args = processor.args
+        .iter()
+        .map(|arg| substitute_argument(arg, &data, &libraries_dir))
+        .collect::<Result<Vec<_>, _>>()?;
+
+      info!("Running processor {}", processor.jar);
+      debug!("{} -cp {} {} {}", java_path.display(), classpath, main_class, args.join(" "));
+      let output = Command::new(java_path).current_dir(game_dir).arg("-cp").arg(classpath).arg(&main_class).args(&args).output()?;
+      if !output.status.success() {
+        return Err(ModLoaderError::ProcessorFailed {
+          processor: processor.jar.to_string(),
+          code: output.status.code(),
+          stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        });
+      }
+
+      for (path, sha1) in &outputs {
+        if !output_matches(Path::new(path), sha1) {
+          return Err(ModLoaderError::OutputMismatch { path: PathBuf::from(path), expected: sha1.clone() });
+        }
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Downloads an installer jar, checked against the `.sha1` published next to it
+pub async fn download_installer(client: &Client, url: &str, target: &Path, reporter: &ProgressReporter) -> Result<(), ModLoaderError> {
+  DownloadJob::new("Loader installer")
+    .with_client(client.clone())
+    .with_progress_reporter(reporter)
+    .add_downloadables(vec![Box::new(ChecksummedDownloadable::new(url, target))])
+    .start().await?;
+  Ok(())
+}
+
+fn read_entry(archive: &mut ZipArchive<File>, name: &str) -> Result<Vec<u8>, ModLoaderError> {
+  let mut entry = archive.by_name(name).map_err(|_| ModLoaderError::InvalidInstaller(format!("missing {name}")))?;
+  let mut bytes = vec![];
+  entry.read_to_end(&mut bytes)?;
+  Ok(bytes)
+}
+
+fn path_string(path: &Path) -> String {
+  path.to_string_lossy().to_string()
+}
+
+fn output_matches(path: &Path, sha1: &str) -> bool {
+  let Ok(expected) = Sha1Sum::try_from(sha1.to_string()) else {
+    return false;
+  };
+  File::open(path)
+    .and_then(|mut file| Sha1Sum::from_reader(&mut file))
+    .is_ok_and(|actual| actual == expected)
+}
+
+/// Reads `Main-Class` from the manifest of a jar
+fn read_main_class(jar: &Path) -> Result<String, ModLoaderError> {
+  let mut archive = ZipArchive::new(File::open(jar)?)?;
+  let manifest = String::from_utf8_lossy(&read_entry(&mut archive, "META-INF/MANIFEST.MF")?).to_string();
+  // Lines longer than 72 bytes continue on the next line, after a space
+  let manifest = manifest.replace("\r\n", "\n").replace("\n ", "");
+  manifest
+    .lines()
+    .find_map(|line| line.strip_prefix("Main-Class:"))
+    .map(|main_class| main_class.trim().to_string())
+    .ok_or_else(|| ModLoaderError::InvalidInstaller(format!("{} has no Main-Class", jar.display())))
+}
+
+/// Substitutes a processor argument or data value:
+/// - `[group:artifact:version]` is the path of the library
+/// - `'literal'` is the quoted text
+/// - `{KEY}` is replaced by the data value anywhere in the argument
+pub fn substitute_argument(arg: &str, data: &HashMap<String, String>, libraries_dir: &Path) -> Result<String, ModLoaderError> {
+  if let Some(descriptor) = arg.strip_prefix('[').and_then(|arg| arg.strip_suffix(']')) {
+    let artifact = Artifact::try_from(descriptor.to_string()).map_err(ModLoaderError::InvalidInstaller)?;
+    return Ok(path_string(&artifact.get_local_path(libraries_dir)));
+  }
+  if let Some(literal) = arg.strip_prefix('\'').and_then(|arg| arg.strip_suffix('\'')) {
+    return Ok(literal.to_string());
+  }
+
+  let mut result = String::new();
+  let mut rest = arg;
+  while let Some(start) = rest.find('{') {
+    let Some(len) = rest[start + 1..].find('}') else {
+      break;
+    };
+    let key = &rest[start + 1..start + 1 + len];
+    let value = data.get(key).ok_or_else(|| ModLoaderError::InvalidInstaller(format!("unknown data key {key}")))?;
+    result.push_str(&rest[..start]);
+    result.push_str(value);
+    rest = &rest[start + 1 + len + 1..];
+  }
+  result.push_str(rest);
+  Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::Write;
+
+  use zip::{ write::SimpleFileOptions, ZipWriter };
+
+  use super::*;
+
+  #[test]
+  fn test_open_installer() {
+    let dir = temp_dir().join(format!("mlc-forge-installer-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let installer_path = dir.join("forge-installer.jar");
+
+    let profile = serde_json::json!({
+      "spec": 1,
+      "version": "1.20.1-forge-47.2.0",
+      "minecraft": "1.20.1",
+      "json": "/version.json",
+      "data": { "MAPPINGS": { "client": "[de.oceanlabs.mcp:mcp_config:1.20.1-20230612.114412:mappings@txt]", "server": "" } },
+      "processors": [
+        { "sides": ["server"], "jar": "net.minecraftforge:installertools:1.3.0", "args": [] },
+        { "jar": "net.minecraftforge:binarypatcher:1.1.1", "args": ["--clean", "{MINECRAFT_JAR}"], "outputs": {} }
+      ],
+      "libraries": []
+    });
+    let version = serde_json::json!({
+      "id": "1.20.1-forge-47.2.0",
+      "inheritsFrom": "1.20.1",
+      "releaseTime": "2023-10-01T12:00:00+00:00",
+      "time": "2023-10-01T12:00:00+00:00",
+      "type": "release",
+      "mainClass": "cpw.mods.bootstraplauncher.BootstrapLauncher",
+      "libraries": [{
+        "name": "net.minecraftforge:forge:1.20.1-47.2.0:universal",
+        "downloads": { "artifact": { "path": "", "url": "", "sha1": "0000000000000000000000000000000000000000", "size": 0 } }
+      }]
+    });
+    let mut zip = ZipWriter::new(File::create(&installer_path).unwrap());
+    for (name, contents) in [
+      (INSTALL_PROFILE, profile.to_string()),
+      ("version.json", version.to_string()),
+      ("maven/net/minecraftforge/forge/1.20.1-47.2.0/forge-1.20.1-47.2.0-universal.jar", "jar".to_string()),
+    ] {
+      zip.start_file(name, SimpleFileOptions::default()).unwrap();
+      zip.write_all(contents.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+
+    let mut installer = InstallerJar::open(&installer_path).unwrap();
+    assert_eq!(installer.version.id, MCVersion::new("1.20.1-forge-47.2.0"));
+    let client_processors: Vec<_> = installer.profile.processors.iter().filter(|processor| processor.runs_on_client()).collect();
+    assert_eq!(client_processors.len(), 1);
+
+    let libraries_dir = dir.join("libraries");
+    installer.extract_maven(&libraries_dir).unwrap();
+    assert!(libraries_dir.join("net/minecraftforge/forge/1.20.1-47.2.0/forge-1.20.1-47.2.0-universal.jar").is_file());
+
+    let _ = fs::remove_dir_all(dir);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn test_run_processors() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = temp_dir().join(format!("mlc-forge-processors-{}", std::process::id()));
+    let game_dir = dir.join("game");
+    let libraries_dir = game_dir.join("libraries");
+    fs::create_dir_all(&libraries_dir).unwrap();
+    let patched_sha1 = Sha1Sum::from_reader(&mut "patched".as_bytes()).unwrap().to_string();
+
+    let profile = serde_json::json!({
+      "version": "1.20.1-forge-47.2.0",
+      "minecraft": "1.20.1",
+      "data": {
+        "BINPATCH": { "client": "/data/client.lzma", "server": "/data/server.lzma" },
+        "PATCHED": { "client": "[net.minecraftforge:forge:1.20.1-47.2.0:client]", "server": "" },
+        "PATCHED_SHA": { "client": format!("'{patched_sha1}'"), "server": "" }
+      },
+      "processors": [
+        { "sides": ["server"], "jar": "net.minecraftforge:installertools:1.3.0", "args": ["{MISSING}"] },
+        {
+          "sides": ["client"],
+          "jar": "net.minecraftforge:binarypatcher:1.1.1",
+          "classpath": ["net.sf.jopt-simple:jopt-simple:5.0.4"],
+          "args": ["--clean", "{MINECRAFT_JAR}", "--patch", "{BINPATCH}", "--output", "{PATCHED}", "--side", "{SIDE}"],
+          "outputs": { "{PATCHED}": "{PATCHED_SHA}" }
+        }
+      ]
+    });
+    let version = serde_json::json!({
+      "id": "1.20.1-forge-47.2.0",
+      "inheritsFrom": "1.20.1",
+      "releaseTime": "2023-10-01T12:00:00+00:00",
+      "time": "2023-10-01T12:00:00+00:00",
+      "type": "release",
+      "libraries": []
+    });
+    let installer_path = dir.join("forge-installer.jar");
+    let mut zip = ZipWriter::new(File::create(&installer_path).unwrap());
+    for (name, contents) in [(INSTALL_PROFILE, profile.to_string()), ("version.json", version.to_string()), ("data/client.lzma", "binpatch".to_string())] {
+      zip.start_file(name, SimpleFileOptions::default()).unwrap();
+      zip.write_all(contents.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+
+    let processor_jar = libraries_dir.join("net/minecraftforge/binarypatcher/1.1.1/binarypatcher-1.1.1.jar");
+    fs::create_dir_all(processor_jar.parent().unwrap()).unwrap();
+    let mut zip = ZipWriter::new(File::create(&processor_jar).unwrap());
+    zip.start_file("META-INF/MANIFEST.MF", SimpleFileOptions::default()).unwrap();
+    zip.write_all(b"Manifest-Version: 1.0\r\nMain-Class: net.minecraftforge.binarypatcher.Co\r\n nsoleTool\r\n").unwrap();
+    zip.finish().unwrap();
+
+    // Stands in for Java: records its arguments and writes the file passed to `--output`
+    let java_path = dir.join("java");
+    fs::write(
+      &java_path,
+      "#!/bin/sh\nprintf '%s\\n' \"$@\" > args.txt\nwhile [ $# -gt 0 ]; do\n  if [ \"$1\" = --output ]; then mkdir -p \"$(dirname \"$2\")\" && printf patched > \"$2\"; fi\n  shift\ndone\n"
+    ).unwrap();
+    fs::set_permissions(&java_path, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let mut installer = InstallerJar::open(&installer_path).unwrap();
+    let minecraft_jar = game_dir.join("versions/1.20.1/1.20.1.jar");
+    let work_dir = dir.join("work");
+    installer.run_processors(&game_dir, &minecraft_jar, &java_path, &work_dir).unwrap();
+
+    let patched = libraries_dir.join("net/minecraftforge/forge/1.20.1-47.2.0/forge-1.20.1-47.2.0-client.jar");
+    assert_eq!(fs::read_to_string(&patched).unwrap(), "patched");
+    let classpath = [processor_jar.clone(), libraries_dir.join("net/sf/jopt-simple/jopt-simple/5.0.4/jopt-simple-5.0.4.jar")]
+      .map(|path| path_string(&path))
+      .join(":");
+    let binpatch = work_dir.join("data/client.lzma");
+    assert_eq!(fs::read_to_string(&binpatch).unwrap(), "binpatch");
+    let args: Vec<String> = fs::read_to_string(game_dir.join("args.txt")).unwrap().lines().map(str::to_string).collect();
+    assert_eq!(args, [
+      "-cp",
+      &classpath,
+      "net.minecraftforge.binarypatcher.ConsoleTool",
+      "--clean",
+      &path_string(&minecraft_jar),
+      "--patch",
+      &path_string(&binpatch),
+      "--output",
+      &path_string(&patched),
+      "--side",
+      "client",
+    ]);
+
+    // Outputs with a matching SHA-1 aren't generated again
+    fs::remove_file(game_dir.join("args.txt")).unwrap();
+    installer.run_processors(&game_dir, &minecraft_jar, &java_path, &work_dir).unwrap();
+    assert!(!game_dir.join("args.txt").exists());
+
+    fs::write(&patched, "outdated").unwrap();
+    installer.profile.data.get_mut("PATCHED_SHA").unwrap().client = "'0000000000000000000000000000000000000000'".to_string();
+    assert!(matches!(
+      installer.run_processors(&game_dir, &minecraft_jar, &java_path, &work_dir),
+      Err(ModLoaderError::OutputMismatch { .. })
+    ));
+
+    let _ = fs::remove_dir_all(dir);
+  }
+
+  #[test]
+  fn test_substitute_argument() {
+    let libraries_dir = Path::new("/libraries");
+    let data = HashMap::from([
+      ("SIDE".to_string(), "client".to_string()),
+      ("MAPPINGS".to_string(), "/libraries/mappings.txt".to_string()),
+    ]);
+
+    let library = substitute_argument("[net.minecraft:client:1.20.1-20230612.114412:slim]", &data, libraries_dir).unwrap();
+    assert_eq!(
+      PathBuf::from(library),
+      libraries_dir.join("net/minecraft/client/1.20.1-20230612.114412/client-1.20.1-20230612.114412-slim.jar")
+    );
+    let zip = substitute_argument("[de.oceanlabs.mcp:mcp_config:1.20.1@zip]", &data, libraries_dir).unwrap();
+    assert!(zip.ends_with("mcp_config-1.20.1.zip"));
+    assert_eq!(substitute_argument("'0123abcd'", &data, libraries_dir).unwrap(), "0123abcd");
+    assert_eq!(substitute_argument("--side={SIDE}", &data, libraries_dir).unwrap(), "--side=client");
+    assert_eq!(substitute_argument("{MAPPINGS}", &data, libraries_dir).unwrap(), "/libraries/mappings.txt");
+    assert!(substitute_argument("{MISSING}", &data, libraries_dir).is_err());
+  }
+}
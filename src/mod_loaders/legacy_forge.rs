@@ -0,0 +1,254 @@
+use std::{ fs::{ self, File }, io::{ self, Read }, path::{ Path, PathBuf } };
+
+use log::info;
+use serde::{ Deserialize, Serialize };
+use serde_json::Value;
+use zip::ZipArchive;
+
+use crate::{
+  json::{ manifest::{ artifact::Artifact, download::DownloadInfo, library::{ Library, LibraryDownloadInfo }, VersionManifest }, MCVersion, Sha1Sum },
+  version_manager::{
+    downloader::{ download_job::DownloadJob, progress::ProgressReporter, utils::create_lib_downloadable },
+    maven::MAVEN_CENTRAL_URL,
+    VersionManager,
+  },
+};
+
+use super::{ error::ModLoaderError, forge::FORGE_MAVEN_URL, installer::INSTALL_PROFILE };
+
+/// Old host of the Forge Maven, still referenced by legacy installers
+const LEGACY_FORGE_MAVEN_URL: &str = "files.minecraftforge.net/maven";
+
+/// The `install_profile.json` of a pre-1.13 Forge installer
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LegacyInstallProfile {
+  pub install: LegacyInstall,
+  /// The version manifest, with Forge-specific library fields
+  pub version_info: Value,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LegacyInstall {
+  /// Maven coordinates of the universal jar once installed
+  pub path: Artifact,
+  /// Name of the universal jar inside the installer
+  pub file_path: String,
+  pub minecraft: MCVersion,
+  pub target: MCVersion,
+}
+
+/// A library of a legacy `versionInfo`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LegacyLibrary {
+  #[serde(flatten)]
+  pub library: Library,
+  /// Installed by the client installer, the other libraries are downloaded by the launcher
+  #[serde(default)]
+  pub clientreq: bool,
+  #[serde(default)]
+  pub serverreq: bool,
+  /// Valid SHA-1s of the jar. Forge only publishes these libraries as `.pack.xz`
+  #[serde(default)]
+  pub checksums: Vec<String>,
+}
+
+/// An opened pre-1.13 Forge installer jar
+pub struct LegacyInstallerJar {
+  pub path: PathBuf,
+  pub profile: LegacyInstallProfile,
+  archive: ZipArchive<File>,
+}
+
+/// Returns whether the installer uses the pre-1.13 `install`/`versionInfo` format
+pub fn is_legacy_installer(path: &Path) -> Result<bool, ModLoaderError> {
+  let mut archive = ZipArchive::new(File::open(path)?)?;
+  let profile: Value = serde_json::from_reader(archive.by_name(INSTALL_PROFILE)?)?;
+  Ok(profile.get("versionInfo").is_some())
+}
+
+impl LegacyInstallerJar {
+  pub fn open(path: &Path) -> Result<Self, ModLoaderError> {
+    let mut archive = ZipArchive::new(File::open(path)?)?;
+    let mut profile = String::new();
+    archive
+      .by_name(INSTALL_PROFILE)
+      .map_err(|_| ModLoaderError::InvalidInstaller(format!("missing {INSTALL_PROFILE}")))?
+      .read_to_string(&mut profile)?;
+    Ok(Self { path: path.to_path_buf(), profile: serde_json::from_str(&profile)?, archive })
+  }
+
+  /// Extracts the universal jar and converts `versionInfo` into a manifest inheriting from the vanilla version
+  pub fn convert(&mut self, libraries_dir: &Path) -> Result<(VersionManifest, Vec<LegacyLibrary>), ModLoaderError> {
+    let install = &self.profile.install;
+    let universal_jar = install.path.get_local_path(libraries_dir);
+    {
+      let mut entry = self.archive
+        .by_name(&install.file_path)
+        .map_err(|_| ModLoaderError::InvalidInstaller(format!("missing {}", install.file_path)))?;
+      fs::create_dir_all(universal_jar.parent().unwrap_or(libraries_dir))?;
+      io::copy(&mut entry, &mut File::create(&universal_jar)?)?;
+    }
+    let universal_sha1 = Sha1Sum::from_reader(&mut File::open(&universal_jar)?)?;
+    let universal_size = fs::metadata(&universal_jar)?.len() as i64;
+
+    let mut version_info = self.profile.version_info.clone();
+    let libraries: Vec<LegacyLibrary> = match version_info.as_object_mut().and_then(|info| info.remove("libraries")) {
+      Some(libraries) => serde_json::from_value(libraries)?,
+      None => vec![],
+    };
+    let mut manifest: VersionManifest = serde_json::from_value(version_info)?;
+    manifest.inherits_from.get_or_insert(install.minecraft.clone());
+    manifest.jar.get_or_insert(install.minecraft.clone());
+
+    let libraries: Vec<LegacyLibrary> = libraries
+      .into_iter()
+      .map(|mut legacy| {
+        let library = &mut legacy.library;
+        if library.name.get_descriptor() == install.path.get_descriptor() {
+          // Extracted from the installer, the Forge Maven only has the `-universal` jar
+          library.url = None;
+          let artifact = DownloadInfo { sha1: universal_sha1.clone(), size: universal_size, url: String::new() };
+          library.downloads = Some(LibraryDownloadInfo { artifact: Some(artifact), classifiers: Default::default() });
+        } else if !legacy.checksums.is_empty() && library.name.group_id.join(".") != "net.minecraftforge" {
+          // Forge only publishes these as `.pack.xz`. Pack200 can't be unpacked here, so the plain jars are
+          // downloaded from Maven Central and checked against the installer checksums.
+          library.url = Some(MAVEN_CENTRAL_URL.to_string());
+        } else if let Some(url) = library.url.as_mut().filter(|url| url.contains(LEGACY_FORGE_MAVEN_URL)) {
+          *url = FORGE_MAVEN_URL.to_string();
+        }
+        legacy
+      })
+      .collect();
+    manifest.libraries = libraries
+      .iter()
+      .map(|legacy| legacy.library.clone())
+      .collect();
+    Ok((manifest, libraries))
+  }
+
+  /// Installs the version into the `VersionManager` directory and returns it resolved.
+  ///
+  /// Like the Forge installer, only the `clientreq` libraries are downloaded here; the launcher downloads the others.
+  pub async fn install(mut self, version_manager: &mut VersionManager, reporter: &ProgressReporter) -> Result<VersionManifest, ModLoaderError> {
+    info!("Installing {} from {}", self.profile.install.target, self.path.display());
+    let libraries_dir = version_manager.game_dir.join("libraries");
+    let (manifest, libraries) = self.convert(&libraries_dir)?;
+
+    let platform = &version_manager.platform;
+    let (required, downloadables): (Vec<_>, Vec<_>) = libraries
+      .iter()
+      .filter(|legacy| legacy.clientreq)
+      .filter_map(|legacy| Some((legacy, create_lib_downloadable(&legacy.library, &version_manager.game_dir, platform)?)))
+      .unzip();
+    DownloadJob::new("Loader libraries")
+      .with_client(version_manager.client.clone())
+      .with_progress_reporter(reporter)
+      .add_downloadables(downloadables)
+      .start().await?;
+    verify_libraries(&required, &libraries_dir)?;
+
+    version_manager.install_local_version(&manifest)?;
+    Ok(version_manager.resolve_local_version(&manifest.id, false, true).await?)
+  }
+}
+
+/// Checks that the `clientreq` libraries were downloaded and match one of their installer checksums
+fn verify_libraries(libraries: &[&LegacyLibrary], libraries_dir: &Path) -> Result<(), ModLoaderError> {
+  for legacy in libraries {
+    let path = legacy.library.name.get_local_path(libraries_dir);
+    if !path.is_file() {
+      return Err(ModLoaderError::MissingLibrary(legacy.library.name.to_string()));
+    }
+    if legacy.checksums.is_empty() {
+      continue;
+    }
+    let actual = Sha1Sum::from_reader(&mut File::open(&path)?)?.to_string();
+    if !legacy.checksums.contains(&actual) {
+      return Err(ModLoaderError::LibraryMismatch { library: legacy.library.name.to_string(), path });
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{ env::temp_dir, io::Write };
+
+  use zip::{ write::SimpleFileOptions, ZipWriter };
+
+  use super::*;
+
+  #[test]
+  fn test_convert_legacy_installer() {
+    let dir = temp_dir().join(format!("mlc-legacy-forge-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let installer_path = dir.join("forge-installer.jar");
+
+    let profile = serde_json::json!({
+      "install": {
+        "profileName": "Forge",
+        "target": "1.7.10-Forge10.13.4.1614-1.7.10",
+        "path": "net.minecraftforge:forge:1.7.10-10.13.4.1614-1.7.10",
+        "version": "Forge 10.13.4.1614",
+        "filePath": "forge-1.7.10-10.13.4.1614-1.7.10-universal.jar",
+        "minecraft": "1.7.10"
+      },
+      "versionInfo": {
+        "id": "1.7.10-Forge10.13.4.1614-1.7.10",
+        "time": "2015-06-11T14:17:20-0400",
+        "releaseTime": "1960-01-01T00:00:00-0600",
+        "type": "release",
+        "minecraftArguments": "--username ${auth_player_name} --tweakClass cpw.mods.fml.common.launcher.FMLTweaker",
+        "mainClass": "net.minecraft.launchwrapper.Launch",
+        "assets": "1.7.10",
+        "libraries": [
+          { "name": "net.minecraftforge:forge:1.7.10-10.13.4.1614-1.7.10", "url": "http://files.minecraftforge.net/maven/" },
+          { "name": "net.minecraft:launchwrapper:1.12", "serverreq": true },
+          {
+            "name": "org.scala-lang:scala-library:2.11.1",
+            "url": "http://files.minecraftforge.net/maven/",
+            "checksums": ["0e11da23da3eabab9f4777b9220e60d44c1aab6a"],
+            "serverreq": true,
+            "clientreq": true
+          },
+          { "name": "lzma:lzma:0.0.1", "url": "http://files.minecraftforge.net/maven/", "clientreq": true }
+        ]
+      }
+    });
+    let mut zip = ZipWriter::new(File::create(&installer_path).unwrap());
+    for (name, contents) in [(INSTALL_PROFILE, profile.to_string()), ("forge-1.7.10-10.13.4.1614-1.7.10-universal.jar", "jar".to_string())] {
+      zip.start_file(name, SimpleFileOptions::default()).unwrap();
+      zip.write_all(contents.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+
+    assert!(is_legacy_installer(&installer_path).unwrap());
+    let libraries_dir = dir.join("libraries");
+    let (manifest, libraries) = LegacyInstallerJar::open(&installer_path).unwrap().convert(&libraries_dir).unwrap();
+    assert_eq!(manifest.inherits_from, Some(MCVersion::new("1.7.10")));
+    assert_eq!(manifest.get_jar(), &MCVersion::new("1.7.10"));
+    assert!(manifest.minecraft_arguments.unwrap().contains("FMLTweaker"));
+    assert!(libraries_dir.join("net/minecraftforge/forge/1.7.10-10.13.4.1614-1.7.10/forge-1.7.10-10.13.4.1614-1.7.10.jar").is_file());
+
+    let forge = &manifest.libraries[0];
+    assert!(forge.url.is_none() && forge.downloads.as_ref().unwrap().artifact.as_ref().unwrap().url.is_empty());
+    assert_eq!(manifest.libraries[1].url, None);
+    assert_eq!(manifest.libraries[2].url.as_deref(), Some(MAVEN_CENTRAL_URL));
+    assert_eq!(manifest.libraries[3].url.as_deref(), Some(FORGE_MAVEN_URL));
+    assert!(libraries[2].clientreq && !libraries[1].clientreq);
+
+    let scala = &libraries[2];
+    assert!(matches!(verify_libraries(&[scala], &libraries_dir), Err(ModLoaderError::MissingLibrary(_))));
+    let scala_path = scala.library.name.get_local_path(&libraries_dir);
+    fs::create_dir_all(scala_path.parent().unwrap()).unwrap();
+    fs::write(&scala_path, "pack.xz").unwrap();
+    assert!(matches!(verify_libraries(&[scala], &libraries_dir), Err(ModLoaderError::LibraryMismatch { .. })));
+    let mut checksums = scala.clone();
+    checksums.checksums.push(Sha1Sum::from_reader(&mut File::open(&scala_path).unwrap()).unwrap().to_string());
+    assert!(verify_libraries(&[&checksums], &libraries_dir).is_ok());
+
+    let _ = fs::remove_dir_all(dir);
+  }
+}
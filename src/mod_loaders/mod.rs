@@ -0,0 +1,18 @@
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+
+use error::ModLoaderError;
+
+pub mod error;
+pub mod fabric;
+pub mod quilt;
+pub mod installer;
+pub mod forge;
+pub mod legacy_forge;
+pub mod neoforge;
+
+/// Fetches `<meta_url>/<path>` from a loader meta API
+async fn fetch_meta<T: DeserializeOwned>(client: &Client, meta_url: &str, path: &str) -> Result<T, ModLoaderError> {
+  let url = format!("{meta_url}/{path}");
+  Ok(client.get(url).send().await?.error_for_status()?.json().await?)
+}
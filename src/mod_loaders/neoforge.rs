@@ -0,0 +1,155 @@
+use std::path::{ Path, PathBuf };
+
+use reqwest::Client;
+
+use crate::{
+  json::{ manifest::VersionManifest, MCVersion },
+  version_manager::{ downloader::progress::ProgressReporter, maven::{ MavenClient, MavenMetadata }, VersionManager },
+};
+
+use super::{ error::ModLoaderError, installer::{ download_installer, InstallerJar } };
+
+pub const NEOFORGE_MAVEN_URL: &str = "https://maven.neoforged.net/releases/";
+
+/// The only Minecraft version NeoForge was published for as `net.neoforged:forge`, with Forge's version scheme
+const LEGACY_MINECRAFT_VERSION: &str = "1.20.1";
+
+/// Installs NeoForge by running its installer processors
+#[derive(Debug, Clone)]
+pub struct NeoForgeInstaller {
+  /// Maven repository hosting NeoForge, with a trailing slash
+  pub maven_url: String,
+  pub client: Client,
+}
+
+impl NeoForgeInstaller {
+  pub fn new(client: &Client) -> Self {
+    Self::with_maven_url(client, NEOFORGE_MAVEN_URL)
+  }
+
+  pub fn with_maven_url(client: &Client, maven_url: &str) -> Self {
+    Self { maven_url: format!("{}/", maven_url.trim_end_matches('/')), client: client.clone() }
+  }
+
+  /// Maven directory of the NeoForge artifact used for a Minecraft version
+  fn artifact_dir(minecraft_version: &MCVersion) -> &'static str {
+    if minecraft_version.to_string() == LEGACY_MINECRAFT_VERSION { "net/neoforged/forge" } else { "net/neoforged/neoforge" }
+  }
+
+  /// Prepends the Minecraft version to 1.20.1 versions given without it, e.g. `47.1.106` becomes `1.20.1-47.1.106`.
+  /// Modpacks list them either way, the Maven artifact always has the prefix.
+  pub fn normalize_version(minecraft_version: &MCVersion, neoforge_version: &str) -> String {
+    let minecraft_version = minecraft_version.to_string();
+    if minecraft_version == LEGACY_MINECRAFT_VERSION && !neoforge_version.starts_with(&format!("{minecraft_version}-")) {
+      return format!("{minecraft_version}-{neoforge_version}");
+    }
+    neoforge_version.to_string()
+  }
+
+  /// Maven path of the installer, relative to the repository
+  pub fn installer_path(minecraft_version: &MCVersion, neoforge_version: &str) -> String {
+    let dir = Self::artifact_dir(minecraft_version);
+    let artifact_id = dir.rsplit('/').next().unwrap_or_default();
+    format!("{dir}/{neoforge_version}/{artifact_id}-{neoforge_version}-installer.jar")
+  }
+
+  /// NeoForge versions for a Minecraft version, oldest first as listed by the Maven metadata
+  pub async fn versions(&self, minecraft_version: &MCVersion) -> Result<Vec<String>, ModLoaderError> {
+    let (group_path, artifact_id) = Self::artifact_dir(minecraft_version).rsplit_once('/').unwrap_or_default();
+    let maven = MavenClient::new(&self.client, &[&self.maven_url]);
+    let metadata = maven.fetch_metadata(&group_path.replace('/', "."), artifact_id).await?;
+    Ok(versions_for(metadata, minecraft_version))
+  }
+
+  pub async fn download_installer(
+    &self,
+    libraries_dir: &Path,
+    minecraft_version: &MCVersion,
+    neoforge_version: &str,
+    reporter: &ProgressReporter
+  ) -> Result<PathBuf, ModLoaderError> {
+    let path = Self::installer_path(minecraft_version, neoforge_version);
+    let target = libraries_dir.join(&path);
+    download_installer(&self.client, &format!("{}{path}", self.maven_url), &target, reporter).await?;
+    Ok(target)
+  }
+
+  /// Downloads and runs the installer with `java_path`, returning the resolved NeoForge version
+  pub async fn install(
+    &self,
+    version_manager: &mut VersionManager,
+    minecraft_version: &MCVersion,
+    neoforge_version: &str,
+    java_path: &Path,
+    reporter: &ProgressReporter
+  ) -> Result<VersionManifest, ModLoaderError> {
+    let libraries_dir = version_manager.game_dir.join("libraries");
+    let neoforge_version = Self::normalize_version(minecraft_version, neoforge_version);
+    let installer = self.download_installer(&libraries_dir, minecraft_version, &neoforge_version, reporter).await?;
+    InstallerJar::open(&installer)?.install(version_manager, java_path, reporter).await
+  }
+}
+
+fn versions_for(metadata: MavenMetadata, minecraft_version: &MCVersion) -> Vec<String> {
+  let prefix = version_prefix(minecraft_version);
+  metadata.versions
+    .into_iter()
+    .filter(|version| version.starts_with(&prefix))
+    .collect()
+}
+
+/// Prefix of the NeoForge versions for a Minecraft version: `1.20.1-` for the Forge scheme,
+/// then the Minecraft version without its leading `1.` (`1.21` gives `21.0.`, `1.20.4` gives `20.4.`)
+fn version_prefix(minecraft_version: &MCVersion) -> String {
+  let minecraft_version = minecraft_version.to_string();
+  if minecraft_version == LEGACY_MINECRAFT_VERSION {
+    return format!("{minecraft_version}-");
+  }
+  let version = minecraft_version.strip_prefix("1.").unwrap_or(&minecraft_version);
+  match version.split_once('.') {
+    Some((major, minor)) => format!("{major}.{minor}."),
+    None => format!("{version}.0."),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_neoforge_versions() {
+    let metadata = MavenMetadata::parse(
+      r#"<?xml version="1.0" encoding="UTF-8"?>
+      <metadata>
+        <groupId>net.neoforged</groupId>
+        <artifactId>neoforge</artifactId>
+        <versioning>
+          <latest>21.0.10-beta</latest>
+          <release>21.0.10-beta</release>
+          <versions>
+            <version>20.4.237</version>
+            <version>20.6.119</version>
+            <version>21.0.10-beta</version>
+          </versions>
+        </versioning>
+      </metadata>"#
+    );
+    assert_eq!(metadata.versions, vec!["20.4.237", "20.6.119", "21.0.10-beta"]);
+    assert_eq!(versions_for(metadata.clone(), &MCVersion::new("1.21")), vec!["21.0.10-beta"]);
+    assert_eq!(versions_for(metadata, &MCVersion::new("1.20.6")), vec!["20.6.119"]);
+    assert_eq!(version_prefix(&MCVersion::new("1.20.4")), "20.4.");
+    assert_eq!(version_prefix(&MCVersion::new("1.20.1")), "1.20.1-");
+    assert_eq!(NeoForgeInstaller::normalize_version(&MCVersion::new("1.20.1"), "47.1.106"), "1.20.1-47.1.106");
+    assert_eq!(NeoForgeInstaller::normalize_version(&MCVersion::new("1.20.1"), "1.20.1-47.1.106"), "1.20.1-47.1.106");
+    assert_eq!(NeoForgeInstaller::normalize_version(&MCVersion::new("1.20.4"), "20.4.237"), "20.4.237");
+
+    assert_eq!(
+      NeoForgeInstaller::installer_path(&MCVersion::new("1.20.4"), "20.4.237"),
+      "net/neoforged/neoforge/20.4.237/neoforge-20.4.237-installer.jar"
+    );
+    assert_eq!(
+      NeoForgeInstaller::installer_path(&MCVersion::new("1.20.1"), "1.20.1-47.1.106"),
+      "net/neoforged/forge/1.20.1-47.1.106/forge-1.20.1-47.1.106-installer.jar"
+    );
+  }
+}
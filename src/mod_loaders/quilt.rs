@@ -0,0 +1,115 @@
+use log::info;
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+
+use crate::{ json::{ manifest::VersionManifest, MCVersion }, version_manager::VersionManager };
+
+use super::{ error::ModLoaderError, fabric::{ install_profile, profile_path, CompatibleLoader, GameVersion, LoaderVersion }, fetch_meta };
+
+pub const QUILT_META_URL: &str = "https://meta.quiltmc.org/v3";
+pub const QUILT_MAVEN_URL: &str = "https://maven.quiltmc.org/repository/release/";
+
+/// Client of the Quilt meta API, the Quilt counterpart of `FabricInstaller`
+#[derive(Debug, Clone)]
+pub struct QuiltInstaller {
+  /// Base URL of the meta API, without a trailing slash
+  pub meta_url: String,
+  pub client: Client,
+}
+
+impl QuiltInstaller {
+  pub fn new(client: &Client) -> Self {
+    Self::with_meta_url(client, QUILT_META_URL)
+  }
+
+  pub fn with_meta_url(client: &Client, meta_url: &str) -> Self {
+    Self { meta_url: meta_url.trim_end_matches('/').to_string(), client: client.clone() }
+  }
+
+  async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, ModLoaderError> {
+    fetch_meta(&self.client, &self.meta_url, path).await
+  }
+
+  /// Game versions supported by Quilt, newest first
+  pub async fn game_versions(&self) -> Result<Vec<GameVersion>, ModLoaderError> {
+    self.get("versions/game").await
+  }
+
+  /// Loader versions compatible with a game version, newest first.
+  ///
+  /// Quilt meta has no `stable` flag, betas are recognized by their pre-release suffix.
+  pub async fn loader_versions_for(&self, game_version: &MCVersion) -> Result<Vec<LoaderVersion>, ModLoaderError> {
+    let loaders: Vec<CompatibleLoader> = self.get(&format!("versions/loader/{game_version}")).await?;
+    Ok(
+      loaders
+        .into_iter()
+        .map(|entry| LoaderVersion { stable: !entry.loader.version.contains('-'), ..entry.loader })
+        .collect()
+    )
+  }
+
+  /// Latest stable loader compatible with a game version
+  pub async fn latest_loader(&self, game_version: &MCVersion) -> Result<LoaderVersion, ModLoaderError> {
+    self
+      .loader_versions_for(game_version).await?
+      .into_iter()
+      .find(|loader| loader.stable)
+      .ok_or_else(|| ModLoaderError::VersionNotFound(format!("no stable Quilt loader for {game_version}")))
+  }
+
+  /// Fetches the launcher profile of a loader version, a manifest inheriting from `game_version`
+  pub async fn fetch_profile(&self, game_version: &MCVersion, loader_version: &str) -> Result<VersionManifest, ModLoaderError> {
+    let mut profile = self.get(&profile_path(game_version, loader_version)).await?;
+    normalize_libraries(&mut profile);
+    Ok(profile)
+  }
+
+  /// Installs the profile to `versions/<id>/` and resolves it, installing the vanilla version if needed
+  pub async fn install(
+    &self,
+    version_manager: &mut VersionManager,
+    game_version: &MCVersion,
+    loader_version: &str
+  ) -> Result<VersionManifest, ModLoaderError> {
+    info!("Installing Quilt {loader_version} for {game_version}");
+    let profile = self.fetch_profile(game_version, loader_version).await?;
+    install_profile(version_manager, &profile).await
+  }
+}
+
+/// Quilt libraries (`org.quiltmc:*`) without a download location would fall back to the Mojang
+/// library server, they are pointed to the Quilt Maven instead
+fn normalize_libraries(profile: &mut VersionManifest) {
+  for library in &mut profile.libraries {
+    if library.url.is_none() && library.downloads.is_none() && library.name.group_id.join(".").starts_with("org.quiltmc") {
+      library.url = Some(QUILT_MAVEN_URL.to_string());
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_quilt_profile() {
+    let mut profile: VersionManifest = serde_json::from_value(
+      serde_json::json!({
+        "id": "quilt-loader-0.26.0-1.20.1",
+        "inheritsFrom": "1.20.1",
+        "releaseTime": "2024-06-01T12:00:00+0000",
+        "time": "2024-06-01T12:00:00+0000",
+        "type": "release",
+        "mainClass": "org.quiltmc.loader.impl.launch.knot.KnotClient",
+        "libraries": [
+          { "name": "net.fabricmc:intermediary:1.20.1", "url": "https://maven.fabricmc.net/" },
+          { "name": "org.quiltmc:quilt-loader:0.26.0", "url": "https://maven.quiltmc.org/repository/release/" },
+          { "name": "org.quiltmc:quilt-json5:1.0.4" }
+        ]
+      })
+    ).unwrap();
+    normalize_libraries(&mut profile);
+    assert_eq!(profile.libraries[0].url.as_deref(), Some("https://maven.fabricmc.net/"));
+    assert_eq!(profile.libraries[2].url.as_deref(), Some(QUILT_MAVEN_URL));
+  }
+}
@@ -9,7 +9,15 @@ use crate::{
   },
 };
 
-use std::{ collections::HashMap, env::temp_dir, path::PathBuf, sync::{ Arc, Mutex } };
+use std::{
+  collections::HashMap,
+  env::temp_dir,
+  io::{ BufRead, BufReader, Write },
+  net::TcpListener,
+  path::PathBuf,
+  sync::{ Arc, Mutex },
+  thread,
+};
 use chrono::{ Duration, Timelike, Utc };
 use futures::{ stream, StreamExt };
 use log::{ debug, error, info, trace, LevelFilter };
@@ -20,6 +28,35 @@ pub fn setup_logger() {
   let _ = SimpleLogger::new().env().with_level(LevelFilter::Debug).init();
 }
 
+/// Serves `files` by path over HTTP on a local port and returns the base URL, e.g. `http://127.0.0.1:1234`
+pub fn serve_files(files: HashMap<String, Vec<u8>>) -> String {
+  let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+  let url = format!("http://{}", listener.local_addr().unwrap());
+  thread::spawn(move || {
+    for mut stream in listener.incoming().flatten() {
+      let mut reader = BufReader::new(&stream);
+      let mut request_line = String::new();
+      let _ = reader.read_line(&mut request_line);
+      let mut header = String::new();
+      while reader.read_line(&mut header).is_ok_and(|len| len > 2) {
+        header.clear();
+      }
+
+      let mut request = request_line.split_whitespace();
+      let (method, path) = (request.next().unwrap_or_default(), request.next().unwrap_or_default());
+      let (status, body) = match files.get(path) {
+        Some(body) => ("200 OK", body.as_slice()),
+        None => ("404 Not Found", [].as_slice()),
+      };
+      let _ = write!(stream, "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", body.len());
+      if method != "HEAD" {
+        let _ = stream.write_all(body);
+      }
+    }
+  });
+  url
+}
+
 pub fn create_progress_reporter() -> ProgressReporter {
   let progress: Arc<Mutex<Option<(String, usize, usize)>>> = Arc::default();
 
@@ -44,23 +81,17 @@ pub fn create_progress_reporter() -> ProgressReporter {
         } else {
           let mut taken = progress.take().unwrap_or_default();
           match event {
-            Event::Status(status) => {
-              if taken.0 != status {
-                taken.0 = status;
-                print_progress(&taken.0, taken.1, taken.2);
-              }
+            Event::Status(status) if taken.0 != status => {
+              taken.0 = status;
+              print_progress(&taken.0, taken.1, taken.2);
             }
-            Event::Progress(progress) => {
-              if taken.1 != progress {
-                taken.1 = progress;
-                print_progress(&taken.0, progress, taken.2);
-              }
+            Event::Progress(progress) if taken.1 != progress => {
+              taken.1 = progress;
+              print_progress(&taken.0, progress, taken.2);
             }
-            Event::Total(total) => {
-              if taken.2 != total {
-                taken.2 = total;
-                print_progress(&taken.0, taken.1, total);
-              }
+            Event::Total(total) if taken.2 != total => {
+              taken.2 = total;
+              print_progress(&taken.0, taken.1, total);
             }
             Event::Setup { status, total } => {
               taken = (status, 0, total.unwrap_or(0));
@@ -129,7 +160,7 @@ async fn test_game() -> Result<(), Box<dyn std::error::Error>> {
   reporter.progress(1);
 
   let manifest = version_manager.resolve_local_version(&mc_version, true, false).await?;
-  if !manifest.applies_to_current_environment(&env_features) {
+  if !manifest.applies_to_environment(&env_features, &version_manager.platform) {
     return Err(format!("Version {} is is incompatible with the current environment", mc_version).into());
   }
   reporter.done();
@@ -156,7 +187,8 @@ async fn test_game() -> Result<(), Box<dyn std::error::Error>> {
     }
     if Utc::now() - start_time > Duration::seconds(15) {
       let mut child = process.into_inner();
-      child.kill()?;
+      let _ = child.kill();
+      let _ = child.wait();
       break (None, child);
     }
   };
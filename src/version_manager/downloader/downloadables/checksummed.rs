@@ -1,11 +1,14 @@
-use std::{ ffi::OsStr, path::{ Path, PathBuf }, sync::{ Arc, Mutex } };
+use std::{ ffi::OsStr, fs::{ self, File }, path::{ Path, PathBuf }, sync::{ Arc, Mutex } };
 
 use async_trait::async_trait;
+use log::info;
 use reqwest::Client;
 
-use super::{ error::HashError, Downloadable, DownloadableMonitor };
+use crate::version_manager::maven::{ fetch_checksum, Checksum };
 
-/// Both the file and the checksum are on the remote server
+use super::{ error::HashError, DownloadError, Downloadable, DownloadableMonitor };
+
+/// Both the file and the checksum are on the remote server, as a `.sha256` or `.sha1` sidecar
 pub struct ChecksummedDownloadable {
   pub url: String,
   pub target_file: PathBuf,
@@ -64,9 +67,84 @@ impl Downloadable for ChecksummedDownloadable {
   }
 
   async fn get_expected_hash(&self, client: &Client) -> Result<Option<Vec<u8>>, HashError> {
-    let url = format!("{}.sha1", self.url);
-    let response = client.get(url).send().await?.error_for_status()?;
-    let hash = response.text().await?;
-    Ok(Some(hex::decode(hash)?))
+    Ok(Some(fetch_checksum(client, &self.url).await?.as_bytes().to_vec()))
+  }
+
+  async fn download(&self, client: &Client) -> Result<(), DownloadError> {
+    let target = &self.target_file;
+    let checksum = fetch_checksum(client, &self.url).await.map_err(HashError::from)?;
+    let local_digest = |checksum: &Checksum| -> Result<Vec<u8>, HashError> { Ok(checksum.digest(&mut File::open(target)?)?) };
+
+    if target.is_file() {
+      if local_digest(&checksum)? == checksum.as_bytes() {
+        info!("Local file matches hash, using it");
+        return Ok(());
+      }
+      fs::remove_file(target).map_err(DownloadError::RemoveFile)?;
+    }
+
+    self.try_download(client).await?;
+    let actual = local_digest(&checksum)?;
+    if actual != checksum.as_bytes() {
+      let _ = fs::remove_file(target);
+      return Err(DownloadError::ChecksumMismatch { expected: checksum.as_bytes().to_vec(), actual });
+    }
+    info!("Downloaded successfully and checksum matched");
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{ collections::HashMap, env::temp_dir };
+
+  use sha2::{ Digest, Sha256 };
+
+  use crate::{ json::Sha1Sum, tests::serve_files };
+
+  use super::*;
+
+  #[tokio::test]
+  async fn test_sha256_sidecar() {
+    let sha256 = hex::encode(Sha256::digest(b"library"));
+    let url = serve_files(HashMap::from([
+      ("/lib.jar".to_string(), b"library".to_vec()),
+      ("/lib.jar.sha256".to_string(), sha256.into_bytes()),
+      ("/bad.jar".to_string(), b"library".to_vec()),
+      ("/bad.jar.sha256".to_string(), hex::encode([0u8; 32]).into_bytes()),
+      // Matches, but the `.sha256` takes precedence
+      ("/bad.jar.sha1".to_string(), Sha1Sum::from_reader(&mut b"library".as_slice()).unwrap().to_string().into_bytes()),
+    ]));
+    let dir = temp_dir().join(format!("mlc-checksummed-{}", std::process::id()));
+    let client = Client::new();
+
+    let target = dir.join("lib.jar");
+    ChecksummedDownloadable::new(&format!("{url}/lib.jar"), &target).download(&client).await.unwrap();
+    assert_eq!(fs::read(&target).unwrap(), b"library");
+    // Kept, as it matches
+    ChecksummedDownloadable::new(&format!("{url}/lib.jar"), &target).download(&client).await.unwrap();
+
+    let target = dir.join("bad.jar");
+    let result = ChecksummedDownloadable::new(&format!("{url}/bad.jar"), &target).download(&client).await;
+    assert!(matches!(result, Err(DownloadError::ChecksumMismatch { .. })));
+    assert!(!target.exists());
+
+    let _ = fs::remove_dir_all(dir);
+  }
+
+  #[tokio::test]
+  async fn test_sha1_fallback() {
+    let sha1 = Sha1Sum::from_reader(&mut b"library".as_slice()).unwrap();
+    let url = serve_files(HashMap::from([
+      ("/lib.jar.sha1".to_string(), sha1.to_string().into_bytes()),
+      ("/other.jar.sha1".to_string(), sha1.to_string().into_bytes()),
+    ]));
+    let client = Client::new();
+
+    assert_eq!(fetch_checksum(&client, &format!("{url}/lib.jar")).await.unwrap().as_bytes(), sha1.as_slice());
+    // The repository doesn't publish `.sha256`, it isn't requested anymore
+    assert!(crate::version_manager::maven::skips_sha256(&format!("{url}/other.jar")));
+    assert_eq!(fetch_checksum(&client, &format!("{url}/other.jar")).await.unwrap().as_bytes(), sha1.as_slice());
+    assert!(fetch_checksum(&client, &format!("{url}/missing.jar")).await.is_err());
   }
 }
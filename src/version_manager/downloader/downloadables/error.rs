@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use crate::version_manager::error::MavenError;
+
 #[derive(Debug, Error)]
 pub enum DownloadError {
   #[error("Failed to write destination file: {0}")] WriteFile(#[source] std::io::Error),
@@ -23,4 +25,5 @@ pub enum HashError {
     actual: usize,
   },
   #[error("Failed to parse expected hash: {0}")] ParseHash(#[from] hex::FromHexError),
+  #[error("Failed to fetch expected hash: {0}")] Maven(#[from] MavenError),
 }
@@ -44,7 +44,7 @@ impl ClientDownloader {
 
     if let Ok(mut file) = File::open(&index_file) {
       let sha1 = Sha1Sum::from_reader(&mut file).map_err(HashError::ChecksumFile)?;
-      if !index_info.sha1.contains(&sha1) {
+      if index_info.sha1 != sha1 {
         warn!("Asset index file is invalid, redownloading");
         fs::remove_file(&index_file).map_err(DownloadError::RemoveFile)?;
       }
@@ -62,9 +62,9 @@ impl ClientDownloader {
       sha1.update(&bytes);
       let actual = Sha1Sum::from(sha1);
 
-      if !index_info.sha1.contains(&actual) {
+      if index_info.sha1 != actual {
         let _ = fs::remove_file(&index_file);
-        return Err(DownloadError::ChecksumMismatch { expected: Vec::from(index_info.sha1.iter().map(|s| s.to_string()).collect::<Vec<_>>().join(",")), actual: actual.into() });
+        Err(DownloadError::ChecksumMismatch { expected: index_info.sha1.clone().into(), actual: actual.into() })
       } else {
         Ok(serde_json::from_slice(&bytes).map_err(|err| DownloadError::Other(Box::new(err)))?)
       }
@@ -85,12 +85,11 @@ impl ClientDownloader {
   ///
   /// # Errors
   /// This function will return an error if any part of the download process fails.
-
   pub async fn download_version(&self, local_version: &VersionManifest, version_manager: &VersionManager) -> Result<(), error::Error> {
-    let VersionManager { game_dir, env_features, .. } = version_manager;
+    let VersionManager { game_dir, env_features, platform, .. } = version_manager;
     let asset_index = self.get_asset_index(local_version, game_dir).await?;
 
-    let mut libs = get_library_downloadables(game_dir, local_version, env_features, None);
+    let mut libs = get_library_downloadables(game_dir, local_version, env_features, platform);
     libs.push(get_jar_downloadable(game_dir, local_version));
 
     let version_job = self.create_download_job("Version & Libraries").add_downloadables(libs);
@@ -5,14 +5,13 @@ use reqwest::Url;
 use crate::{
   version_manager::downloader::downloadables::{ AssetDownloadable, ChecksummedDownloadable, Downloadable, EtagDownloadable, PreHashedDownloadable },
   json::{
-    manifest::{ assets::AssetIndex, download::{ DownloadInfo, DownloadType }, library::Library, rule::OperatingSystem, VersionManifest },
+    manifest::{ assets::AssetIndex, download::{ DownloadInfo, DownloadType }, library::Library, rule::Platform, VersionManifest },
     EnvironmentFeatures,
-    VersionInfo,
   },
 };
 
 pub fn get_jar_downloadable(game_dir: &Path, local_version: &VersionManifest) -> Box<dyn Downloadable + Send + Sync> {
-  let version_id = local_version.get_id().to_string();
+  let version_id = local_version.get_jar().to_string();
   let jar_path = game_dir.join("versions").join(&version_id).join(format!("{}.jar", &version_id));
 
   if let Some(DownloadInfo { sha1, url, .. }) = local_version.get_download_url(DownloadType::Client) {
@@ -27,13 +26,12 @@ pub fn get_library_downloadables(
   game_dir: &Path,
   local_version: &VersionManifest,
   env_features: &EnvironmentFeatures,
-  os: Option<OperatingSystem>
+  platform: &Platform
 ) -> Vec<Box<dyn Downloadable + Send + Sync>> {
-  let os = os.unwrap_or(OperatingSystem::get_current_platform());
   local_version
-    .get_relevant_libraries(env_features)
+    .get_relevant_libraries(env_features, platform)
     .into_iter()
-    .flat_map(|lib| create_lib_downloadable(lib, game_dir, &os))
+    .flat_map(|lib| create_lib_downloadable(lib, game_dir, platform))
     .collect()
 }
 
@@ -50,9 +48,9 @@ pub fn get_asset_downloadables(game_dir: &Path, asset_index: &AssetIndex) -> Vec
   downloadables
 }
 
-pub fn create_lib_downloadable(lib: &Library, game_dir: &Path, os: &OperatingSystem) -> Option<Box<dyn Downloadable + Send + Sync>> {
+pub fn create_lib_downloadable(lib: &Library, game_dir: &Path, platform: &Platform) -> Option<Box<dyn Downloadable + Send + Sync>> {
   // If the lib has a natives field, but the os is not supported, return None immediately
-  let classifier = lib.get_artifact_classifier(os)?;
+  let classifier = lib.get_artifact_classifier(&platform.os, &platform.arch)?;
 
   let libraries_dir = game_dir.join("libraries");
   let artifact_path = lib.get_artifact_path(classifier);
@@ -61,7 +59,9 @@ pub fn create_lib_downloadable(lib: &Library, game_dir: &Path, os: &OperatingSys
   // If the lib has a single url
   if let Some(url) = &lib.url {
     let mut url = Url::parse(url).ok()?;
-    url.set_path(&artifact_path);
+    // The Maven repository can be below a path, e.g. https://maven.quiltmc.org/repository/release/
+    let repository_path = url.path().trim_end_matches('/').to_string();
+    url.set_path(&format!("{repository_path}/{artifact_path}"));
     let downloadable = ChecksummedDownloadable::new(url.as_str(), &file_path);
     return Some(Box::new(downloadable));
   }
@@ -74,7 +74,8 @@ pub fn create_lib_downloadable(lib: &Library, game_dir: &Path, os: &OperatingSys
 
   // If the lib has multiple urls (like for each OS)
   // We obtain the download info for the OS
-  if let Some(DownloadInfo { url, sha1, .. }) = lib.get_download_info(os) {
+  // An empty url is used by loader installers for libraries they generate locally
+  if let Some(DownloadInfo { url, sha1, .. }) = lib.get_download_info(&platform.os, &platform.arch).filter(|info| !info.url.is_empty()) {
     let downloadable = PreHashedDownloadable::new(&url, &file_path, sha1);
     Some(Box::new(downloadable))
   } else {
@@ -37,3 +37,11 @@ pub enum ResolveManifestError {
   },
   #[error(transparent)] InstallVersionError(#[from] InstallVersionError),
 }
+
+#[derive(Debug, Error)]
+pub enum MavenError {
+  #[error("{0} not found in any repository")] NotFound(String),
+  #[error("failed to fetch: {0}")] FetchError(#[from] reqwest::Error),
+  #[error("invalid checksum file: {0}")] InvalidChecksum(String),
+  #[error(transparent)] IoError(#[from] std::io::Error),
+}
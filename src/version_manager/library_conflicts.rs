@@ -0,0 +1,217 @@
+use std::cmp::Ordering;
+
+use log::info;
+
+use crate::json::{ manifest::{ artifact::Artifact, library::Library }, MCVersion };
+
+/// Which library is kept when an inheriting version declares a library also declared by its parent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LibraryConflictStrategy {
+  /// The inheriting version overrides its parent, like mod loaders expect
+  #[default]
+  PreferChild,
+  /// The highest version wins
+  PreferNewest,
+  /// Both are kept, the child's first on the classpath
+  KeepAll,
+}
+
+/// A library dropped while merging an inheriting version with its parent
+#[derive(Debug, Clone)]
+pub struct LibraryConflict {
+  /// The version whose inheritance was being resolved
+  pub version: MCVersion,
+  pub kept: Artifact,
+  pub replaced: Artifact,
+}
+
+/// Libraries are the same if they share `group:artifact:classifier`
+fn library_key(artifact: &Artifact) -> (&[String], &str, Option<&str>) {
+  (&artifact.group_id, &artifact.artifact_id, artifact.classifier.as_deref())
+}
+
+/// Whether the child library is used everywhere the parent library is, so it can replace it.
+/// A child limited to some platforms by its rules must not drop the parent's library on the others.
+fn covers(child: &Library, parent: &Library) -> bool {
+  child.rules.is_empty() || child.rules == parent.rules
+}
+
+/// Merges the libraries of `version` (child) with the resolved libraries of its parent, child first.
+///
+/// Only libraries across the two lists conflict, a manifest may list the same library several times
+/// with different rules (e.g. per-OS natives). With `PreferNewest`, a newer parent library only replaces
+/// a child library with the same rules, and later parent libraries conflicting with the same child are
+/// compared with the one kept so that a single version ends up on the classpath.
+pub(crate) fn merge_libraries(
+  version: &MCVersion,
+  child: Vec<Library>,
+  parent: Vec<Library>,
+  strategy: LibraryConflictStrategy,
+  conflicts: &mut Vec<LibraryConflict>
+) -> Vec<Library> {
+  if strategy == LibraryConflictStrategy::KeepAll {
+    return child.into_iter().chain(parent).collect();
+  }
+
+  // Index in `kept_parents` of the parent library that replaced each child library
+  let mut replacements: Vec<Option<usize>> = vec![None; child.len()];
+  let mut kept_parents: Vec<Library> = vec![];
+  for parent_library in parent {
+    let conflicting = child
+      .iter()
+      .position(|child_library| library_key(&child_library.name) == library_key(&parent_library.name) && covers(child_library, &parent_library));
+    let Some(index) = conflicting else {
+      kept_parents.push(parent_library);
+      continue;
+    };
+
+    let kept = match replacements[index] {
+      Some(replacement) => &kept_parents[replacement],
+      None => &child[index],
+    };
+    let parent_wins =
+      strategy == LibraryConflictStrategy::PreferNewest &&
+      kept.rules == parent_library.rules &&
+      compare_versions(&parent_library.name.version, &kept.name.version) == Ordering::Greater;
+    let conflict = if parent_wins {
+      let conflict = LibraryConflict { version: version.clone(), kept: parent_library.name.clone(), replaced: kept.name.clone() };
+      match replacements[index] {
+        Some(replacement) => kept_parents[replacement] = parent_library,
+        None => {
+          replacements[index] = Some(kept_parents.len());
+          kept_parents.push(parent_library);
+        },
+      }
+      conflict
+    } else {
+      LibraryConflict { version: version.clone(), kept: kept.name.clone(), replaced: parent_library.name }
+    };
+    info!("{}: {} replaces {}", conflict.version, conflict.kept, conflict.replaced);
+    conflicts.push(conflict);
+  }
+
+  child
+    .into_iter()
+    .zip(replacements)
+    .filter(|(_, replacement)| replacement.is_none())
+    .map(|(library, _)| library)
+    .chain(kept_parents)
+    .collect()
+}
+
+/// Compares Maven versions by their numeric and text parts, e.g. `9.5` < `9.6` < `9.10`
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+  let parts = |version: &str| version.split(['.', '-', '_', '+']).map(str::to_string).collect::<Vec<_>>();
+  let (a, b) = (parts(a), parts(b));
+  for (a, b) in a.iter().zip(&b) {
+    let ordering = match (a.parse::<u64>(), b.parse::<u64>()) {
+      (Ok(a), Ok(b)) => a.cmp(&b),
+      // A release is newer than its qualified builds (e.g. `1.0` > `1.0-beta`)
+      (Ok(_), Err(_)) => Ordering::Greater,
+      (Err(_), Ok(_)) => Ordering::Less,
+      (Err(_), Err(_)) => a.cmp(b),
+    };
+    if ordering != Ordering::Equal {
+      return ordering;
+    }
+  }
+  // Extra numeric parts are newer (`1.0.1` > `1.0`), extra qualifiers older (`1.0-beta` < `1.0`)
+  let is_number = |part: &String| part.parse::<u64>().is_ok();
+  match a.len().cmp(&b.len()) {
+    Ordering::Greater if is_number(&a[b.len()]) => Ordering::Greater,
+    Ordering::Greater => Ordering::Less,
+    Ordering::Less if is_number(&b[a.len()]) => Ordering::Less,
+    Ordering::Less => Ordering::Greater,
+    Ordering::Equal => Ordering::Equal,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn libraries(names: &[&str]) -> Vec<Library> {
+    names
+      .iter()
+      .map(|name| serde_json::from_value(serde_json::json!({ "name": name })).unwrap())
+      .collect()
+  }
+
+  fn names(libraries: &[Library]) -> Vec<String> {
+    libraries
+      .iter()
+      .map(|library| library.name.to_string())
+      .collect()
+  }
+
+  #[test]
+  fn test_merge_libraries() {
+    let version = MCVersion::new("fabric-loader-0.15.11-1.20.1");
+    let child = || libraries(&["org.ow2.asm:asm:9.6", "com.google.guava:guava:21.0", "net.fabricmc:fabric-loader:0.15.11"]);
+    let parent = || {
+      libraries(&["org.ow2.asm:asm:9.3", "com.google.guava:guava:31.1-jre", "org.lwjgl:lwjgl:3.3.1", "org.lwjgl:lwjgl:3.3.1:natives-linux"])
+    };
+
+    let mut conflicts = vec![];
+    let merged = merge_libraries(&version, child(), parent(), LibraryConflictStrategy::PreferChild, &mut conflicts);
+    assert_eq!(names(&merged), vec![
+      "org.ow2.asm:asm:9.6",
+      "com.google.guava:guava:21.0",
+      "net.fabricmc:fabric-loader:0.15.11",
+      "org.lwjgl:lwjgl:3.3.1",
+      "org.lwjgl:lwjgl:3.3.1:natives-linux",
+    ]);
+    assert_eq!(conflicts.len(), 2);
+    assert_eq!(conflicts[0].replaced.to_string(), "org.ow2.asm:asm:9.3");
+
+    let mut conflicts = vec![];
+    let merged = merge_libraries(&version, child(), parent(), LibraryConflictStrategy::PreferNewest, &mut conflicts);
+    assert_eq!(names(&merged), vec![
+      "org.ow2.asm:asm:9.6",
+      "net.fabricmc:fabric-loader:0.15.11",
+      "com.google.guava:guava:31.1-jre",
+      "org.lwjgl:lwjgl:3.3.1",
+      "org.lwjgl:lwjgl:3.3.1:natives-linux",
+    ]);
+    assert_eq!(conflicts[1].kept.to_string(), "com.google.guava:guava:31.1-jre");
+
+    let merged = merge_libraries(&version, child(), parent(), LibraryConflictStrategy::KeepAll, &mut vec![]);
+    assert_eq!(merged.len(), 7);
+
+    assert_eq!(compare_versions("9.10", "9.6"), Ordering::Greater);
+    assert_eq!(compare_versions("1.0-beta", "1.0"), Ordering::Less);
+  }
+
+  #[test]
+  fn test_merge_libraries_rules() {
+    let version = MCVersion::new("1.20.1-forge-47.3.0");
+    let linux_only = |name: &str| -> Library {
+      serde_json::from_value(serde_json::json!({ "name": name, "rules": [{ "action": "allow", "os": { "name": "linux" } }] })).unwrap()
+    };
+
+    // A child library limited to Linux keeps the parent's library for the other platforms
+    let mut conflicts = vec![];
+    let child = vec![linux_only("org.ow2.asm:asm:9.6")];
+    let merged = merge_libraries(&version, child, libraries(&["org.ow2.asm:asm:9.3"]), LibraryConflictStrategy::PreferChild, &mut conflicts);
+    assert_eq!(names(&merged), vec!["org.ow2.asm:asm:9.6", "org.ow2.asm:asm:9.3"]);
+    assert!(conflicts.is_empty());
+
+    // Duplicate parent keys are compared with the library that replaced the child, only the newest is kept
+    let mut conflicts = vec![];
+    let parent = libraries(&["org.ow2.asm:asm:9.7", "org.ow2.asm:asm:9.8", "org.ow2.asm:asm:9.5"]);
+    let merged = merge_libraries(&version, libraries(&["org.ow2.asm:asm:9.6"]), parent, LibraryConflictStrategy::PreferNewest, &mut conflicts);
+    assert_eq!(names(&merged), vec!["org.ow2.asm:asm:9.8"]);
+    let replaced = conflicts.iter().map(|conflict| (conflict.kept.to_string(), conflict.replaced.to_string())).collect::<Vec<_>>();
+    assert_eq!(replaced, vec![
+      ("org.ow2.asm:asm:9.7".to_string(), "org.ow2.asm:asm:9.6".to_string()),
+      ("org.ow2.asm:asm:9.8".to_string(), "org.ow2.asm:asm:9.7".to_string()),
+      ("org.ow2.asm:asm:9.8".to_string(), "org.ow2.asm:asm:9.5".to_string()),
+    ]);
+
+    let mut conflicts = vec![];
+    let parent = libraries(&["org.ow2.asm:asm:9.3", "org.ow2.asm:asm:9.3"]);
+    let merged = merge_libraries(&version, libraries(&["org.ow2.asm:asm:9.6"]), parent, LibraryConflictStrategy::PreferNewest, &mut conflicts);
+    assert_eq!(names(&merged), vec!["org.ow2.asm:asm:9.6"]);
+    assert_eq!(conflicts.len(), 2);
+  }
+}
@@ -0,0 +1,316 @@
+use std::{ collections::BTreeSet, fs::File, io::{ copy, Read }, path::Path, sync::Mutex };
+
+use log::debug;
+use regex::Regex;
+use reqwest::{ Client, Url };
+use sha1::{ Digest, Sha1 };
+use sha2::Sha256;
+
+use crate::json::{ manifest::artifact::Artifact, Sha1Sum };
+
+use super::error::MavenError;
+
+pub const MAVEN_CENTRAL_URL: &str = "https://repo1.maven.org/maven2/";
+const SNAPSHOT_SUFFIX: &str = "-SNAPSHOT";
+
+/// A `maven-metadata.xml`, either of an artifact (versions) or of a snapshot version (timestamped builds)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MavenMetadata {
+  pub group_id: Option<String>,
+  pub artifact_id: Option<String>,
+  pub latest: Option<String>,
+  pub release: Option<String>,
+  /// Versions as listed by the repository, usually oldest first
+  pub versions: Vec<String>,
+  pub snapshot: Option<Snapshot>,
+  pub snapshot_versions: Vec<SnapshotVersion>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+  /// e.g. `20240101.123456`
+  pub timestamp: String,
+  pub build_number: u32,
+}
+
+/// The file version of a snapshot for a classifier and extension
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotVersion {
+  pub classifier: Option<String>,
+  pub extension: String,
+  /// e.g. `1.0-20240101.123456-3`
+  pub value: String,
+}
+
+impl MavenMetadata {
+  /// Parses the elements used by launchers, other elements are ignored
+  pub fn parse(xml: &str) -> Self {
+    let snapshot = first_tag(xml, "snapshot").and_then(|snapshot| {
+      Some(Snapshot { timestamp: first_tag(&snapshot, "timestamp")?, build_number: first_tag(&snapshot, "buildNumber")?.parse().ok()? })
+    });
+    let snapshot_versions = tag_values(xml, "snapshotVersion")
+      .into_iter()
+      .filter_map(|entry| {
+        Some(SnapshotVersion {
+          classifier: first_tag(&entry, "classifier").filter(|classifier| !classifier.is_empty()),
+          extension: first_tag(&entry, "extension")?,
+          value: first_tag(&entry, "value")?,
+        })
+      })
+      .collect();
+
+    Self {
+      group_id: first_tag(xml, "groupId"),
+      artifact_id: first_tag(xml, "artifactId"),
+      latest: first_tag(xml, "latest"),
+      release: first_tag(xml, "release"),
+      versions: first_tag(xml, "versions")
+        .map(|versions| tag_values(&versions, "version"))
+        .unwrap_or_default(),
+      snapshot,
+      snapshot_versions,
+    }
+  }
+
+  /// File version of a snapshot build, from `snapshotVersions` or else from the `snapshot` timestamp
+  pub fn snapshot_file_version(&self, version: &str, classifier: Option<&str>, extension: &str) -> Option<String> {
+    let listed = self.snapshot_versions
+      .iter()
+      .find(|snapshot| snapshot.classifier.as_deref() == classifier && snapshot.extension == extension)
+      .map(|snapshot| snapshot.value.clone());
+    listed.or_else(|| {
+      let Snapshot { timestamp, build_number } = self.snapshot.as_ref()?;
+      Some(format!("{}-{timestamp}-{build_number}", version.strip_suffix(SNAPSHOT_SUFFIX)?))
+    })
+  }
+}
+
+/// Contents of the elements named `tag`, trimmed
+fn tag_values(xml: &str, tag: &str) -> Vec<String> {
+  let regex = Regex::new(&format!(r"(?s)<{tag}>(.*?)</{tag}>")).unwrap();
+  regex
+    .captures_iter(xml)
+    .map(|captures| captures[1].trim().to_string())
+    .collect()
+}
+
+fn first_tag(xml: &str, tag: &str) -> Option<String> {
+  tag_values(xml, tag).into_iter().next()
+}
+
+/// A checksum published next to a Maven file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Checksum {
+  Sha1(Sha1Sum),
+  Sha256([u8; 32]),
+}
+
+impl Checksum {
+  /// Parses a `.sha1` or `.sha256` file, which may be followed by the file name
+  pub fn parse(extension: &str, contents: &str) -> Result<Self, MavenError> {
+    let hash = contents.split_whitespace().next().unwrap_or_default();
+    let invalid = || MavenError::InvalidChecksum(contents.trim().to_string());
+    match extension {
+      "sha1" => Ok(Checksum::Sha1(parse_sha1(contents)?)),
+      "sha256" => {
+        let mut buf = [0u8; 32];
+        hex::decode_to_slice(hash, &mut buf).map_err(|_| invalid())?;
+        Ok(Checksum::Sha256(buf))
+      }
+      _ => Err(invalid()),
+    }
+  }
+
+  pub fn as_bytes(&self) -> &[u8] {
+    match self {
+      Checksum::Sha1(sha1) => sha1.as_slice(),
+      Checksum::Sha256(sha256) => sha256,
+    }
+  }
+
+  /// Hashes `reader` with the algorithm of this checksum
+  pub fn digest<R: Read>(&self, reader: &mut R) -> Result<Vec<u8>, std::io::Error> {
+    Ok(match self {
+      Checksum::Sha1(_) => {
+        let mut hasher = Sha1::new();
+        copy(reader, &mut hasher)?;
+        hasher.finalize().to_vec()
+      }
+      Checksum::Sha256(_) => {
+        let mut hasher = Sha256::new();
+        copy(reader, &mut hasher)?;
+        hasher.finalize().to_vec()
+      }
+    })
+  }
+
+  pub fn matches<R: Read>(&self, reader: &mut R) -> Result<bool, MavenError> {
+    Ok(self.digest(reader)? == self.as_bytes())
+  }
+}
+
+/// Origins of the repositories that didn't serve a `.sha256` sidecar, only their `.sha1` is fetched afterwards
+static NO_SHA256_ORIGINS: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+fn origin(url: &str) -> Option<String> {
+  Url::parse(url).ok().map(|url| url.origin().ascii_serialization())
+}
+
+pub(crate) fn skips_sha256(url: &str) -> bool {
+  origin(url).is_some_and(|origin| NO_SHA256_ORIGINS.lock().unwrap().contains(&origin))
+}
+
+/// Fetches the strongest checksum published next to a file, `.sha256` then `.sha1`.
+///
+/// Any failure to get the `.sha256` falls back to the `.sha1`, and the repository is remembered so that
+/// later files from it don't cost an extra request.
+pub async fn fetch_checksum(client: &Client, url: &str) -> Result<Checksum, MavenError> {
+  if !skips_sha256(url) {
+    let response = client.get(format!("{url}.sha256")).send().await?;
+    if response.status().is_success() {
+      return Checksum::parse("sha256", &response.text().await?);
+    }
+    debug!("No .sha256 for {url} ({}), using .sha1 for its repository", response.status());
+    if let Some(origin) = origin(url) {
+      NO_SHA256_ORIGINS.lock().unwrap().insert(origin);
+    }
+  }
+  match fetch_text(client, &format!("{url}.sha1")).await? {
+    Some(contents) => Checksum::parse("sha1", &contents),
+    None => Err(MavenError::NotFound(format!("checksum of {url}"))),
+  }
+}
+
+/// Fetches a text file, `None` if the server doesn't have it
+async fn fetch_text(client: &Client, url: &str) -> Result<Option<String>, MavenError> {
+  let response = client.get(url).send().await?;
+  if response.status() == reqwest::StatusCode::NOT_FOUND {
+    return Ok(None);
+  }
+  Ok(Some(response.error_for_status()?.text().await?))
+}
+
+fn parse_sha1(contents: &str) -> Result<Sha1Sum, MavenError> {
+  let mut buf = [0u8; 20];
+  let hash = contents.split_whitespace().next().unwrap_or_default();
+  hex::decode_to_slice(hash, &mut buf).map_err(|_| MavenError::InvalidChecksum(contents.trim().to_string()))?;
+  Ok(Sha1Sum::new(buf))
+}
+
+/// Looks up artifacts in a list of Maven repositories, in order
+#[derive(Debug, Clone)]
+pub struct MavenClient {
+  /// Repository URLs, with a trailing slash
+  pub repositories: Vec<String>,
+  pub client: Client,
+}
+
+impl MavenClient {
+  pub fn new(client: &Client, repositories: &[&str]) -> Self {
+    Self {
+      repositories: repositories
+        .iter()
+        .map(|repository| format!("{}/", repository.trim_end_matches('/')))
+        .collect(),
+      client: client.clone(),
+    }
+  }
+
+  /// Fetches the metadata of `group_id:artifact_id` from the first repository that has it
+  pub async fn fetch_metadata(&self, group_id: &str, artifact_id: &str) -> Result<MavenMetadata, MavenError> {
+    let path = format!("{}/{artifact_id}/maven-metadata.xml", group_id.replace('.', "/"));
+    for repository in &self.repositories {
+      if let Some(xml) = fetch_text(&self.client, &format!("{repository}{path}")).await? {
+        return Ok(MavenMetadata::parse(&xml));
+      }
+    }
+    Err(MavenError::NotFound(format!("{group_id}:{artifact_id}")))
+  }
+
+  /// Returns the download URL of an artifact from the first repository that has it,
+  /// resolving `-SNAPSHOT` versions to their latest timestamped build
+  pub async fn resolve(&self, artifact: &Artifact) -> Result<String, MavenError> {
+    let Artifact { group_id, artifact_id, version, classifier, ext, .. } = artifact;
+    let version_dir = format!("{}/{artifact_id}/{version}", group_id.join("/"));
+
+    for repository in &self.repositories {
+      let file_version = if version.ends_with(SNAPSHOT_SUFFIX) {
+        let Some(xml) = fetch_text(&self.client, &format!("{repository}{version_dir}/maven-metadata.xml")).await? else {
+          continue;
+        };
+        MavenMetadata::parse(&xml).snapshot_file_version(version, classifier.as_deref(), ext).unwrap_or_else(|| version.clone())
+      } else {
+        version.clone()
+      };
+
+      let classifier = classifier.as_ref().map(|classifier| format!("-{classifier}")).unwrap_or_default();
+      let url = format!("{repository}{version_dir}/{artifact_id}-{file_version}{classifier}.{ext}");
+      let response = self.client.head(&url).send().await?;
+      if response.status().is_success() {
+        return Ok(url);
+      }
+      debug!("{artifact} not found in {repository}");
+    }
+    Err(MavenError::NotFound(artifact.to_string()))
+  }
+
+  /// Fetches the strongest checksum published for a file, `.sha256` then `.sha1`
+  pub async fn fetch_checksum(&self, url: &str) -> Result<Checksum, MavenError> {
+    fetch_checksum(&self.client, url).await
+  }
+
+  /// Checks a downloaded file against the checksum published next to `url`
+  pub async fn verify_file(&self, url: &str, path: &Path) -> Result<bool, MavenError> {
+    let checksum = self.fetch_checksum(url).await?;
+    checksum.matches(&mut File::open(path)?)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_maven_metadata() {
+    let metadata = MavenMetadata::parse(
+      r#"<?xml version="1.0" encoding="UTF-8"?>
+      <metadata>
+        <groupId>net.fabricmc</groupId>
+        <artifactId>fabric-loader</artifactId>
+        <versioning>
+          <latest>0.16.0</latest>
+          <release>0.15.11</release>
+          <versions>
+            <version>0.15.10</version>
+            <version>0.15.11</version>
+            <version>0.16.0</version>
+          </versions>
+          <lastUpdated>20240601120000</lastUpdated>
+        </versioning>
+      </metadata>"#
+    );
+    assert_eq!(metadata.artifact_id.as_deref(), Some("fabric-loader"));
+    assert_eq!(metadata.release.as_deref(), Some("0.15.11"));
+    assert_eq!(metadata.versions, vec!["0.15.10", "0.15.11", "0.16.0"]);
+
+    let snapshot = MavenMetadata::parse(
+      r#"<metadata>
+        <version>1.2-SNAPSHOT</version>
+        <versioning>
+          <snapshot><timestamp>20240101.123456</timestamp><buildNumber>3</buildNumber></snapshot>
+          <snapshotVersions>
+            <snapshotVersion><classifier>sources</classifier><extension>jar</extension><value>1.2-20240101.120000-2</value></snapshotVersion>
+          </snapshotVersions>
+        </versioning>
+      </metadata>"#
+    );
+    assert_eq!(snapshot.snapshot_file_version("1.2-SNAPSHOT", Some("sources"), "jar").as_deref(), Some("1.2-20240101.120000-2"));
+    assert_eq!(snapshot.snapshot_file_version("1.2-SNAPSHOT", None, "jar").as_deref(), Some("1.2-20240101.123456-3"));
+
+    let sha1 = Checksum::parse("sha1", "a9993e364706816aba3e25717850c26c9cd0d89d  abc.jar\n").unwrap();
+    assert!(sha1.matches(&mut "abc".as_bytes()).unwrap());
+    let sha256 = Checksum::parse("sha256", "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad").unwrap();
+    assert!(sha256.matches(&mut "abc".as_bytes()).unwrap());
+    assert!(!sha256.matches(&mut "abd".as_bytes()).unwrap());
+  }
+}
@@ -2,16 +2,19 @@ use std::{ collections::{ HashMap, HashSet }, fs::{ self, create_dir_all, read_d
 
 use downloader::{ download_job::DownloadJob, progress::ProgressReporter, ClientDownloader };
 use error::{ InstallVersionError, LoadVersionError, ResolveManifestError };
+use library_conflicts::{ LibraryConflict, LibraryConflictStrategy };
 use log::{ error, info, warn };
 use remote::{ RawVersionList, RemoteVersionInfo };
 use reqwest::Client;
 use utils::resolve;
 
-use crate::json::{ manifest::{ rule::OperatingSystem, VersionManifest }, EnvironmentFeatures, MCVersion, VersionInfo };
+use crate::json::{ manifest::{ rule::Platform, VersionManifest }, EnvironmentFeatures, MCVersion, VersionInfo };
 
 pub mod downloader;
 pub mod remote;
 pub mod error;
+pub mod maven;
+pub mod library_conflicts;
 
 mod utils;
 
@@ -19,12 +22,17 @@ mod utils;
 pub struct VersionManager {
   pub game_dir: PathBuf,
   pub env_features: EnvironmentFeatures,
+  /// Platform libraries are selected and downloaded for, the running system by default
+  pub platform: Platform,
+  /// How libraries declared by both an inheriting version and its parent are merged
+  pub library_conflict_strategy: LibraryConflictStrategy,
   pub client: Client,
 
   local_cache: Vec<MCVersion>,
   remote_cache: Option<RawVersionList>,
 
   resolved_versions_cache: HashMap<MCVersion, VersionManifest>,
+  library_conflicts: HashMap<MCVersion, Vec<LibraryConflict>>,
 }
 
 impl VersionManager {
@@ -34,11 +42,14 @@ impl VersionManager {
     Self {
       game_dir: game_dir.to_path_buf(),
       env_features: env_features.clone(),
+      platform: Platform::current(),
+      library_conflict_strategy: LibraryConflictStrategy::default(),
       client: client.unwrap_or(DownloadJob::create_http_client(None).unwrap_or_default()),
 
       local_cache: vec![],
       remote_cache: None,
       resolved_versions_cache: HashMap::new(),
+      library_conflicts: HashMap::new(),
     }
   }
 
@@ -73,6 +84,11 @@ impl VersionManager {
       .find(|v| v.get_id() == version_id)
   }
 
+  /// The remote version list, `None` until `VersionManager::refresh` succeeded
+  pub fn remote_version_list(&self) -> Option<&RawVersionList> {
+    self.remote_cache.as_ref()
+  }
+
   /// Retrieves the local version information based on the provided version identifier.
   ///
   /// This function searches through a cached list of local versions, attempting to find
@@ -98,6 +114,11 @@ impl VersionManager {
   pub fn get_resolved_version_cache(&self, version_id: &MCVersion) -> Option<&VersionManifest> {
     self.resolved_versions_cache.get(version_id)
   }
+
+  /// Libraries replaced the last time the inheritances of a version were resolved
+  pub fn get_library_conflicts(&self, version_id: &MCVersion) -> &[LibraryConflict] {
+    self.library_conflicts.get(version_id).map(Vec::as_slice).unwrap_or_default()
+  }
 }
 
 impl VersionManager {
@@ -227,11 +248,29 @@ impl VersionManager {
     self.local_cache.push(version_manifest.get_id().clone());
     Ok(version_manifest)
   }
+
+  /// Writes a manifest created by the launcher (mod loaders, imported instances) to the versions directory
+  pub fn install_local_version(&mut self, version_manifest: &VersionManifest) -> Result<(), InstallVersionError> {
+    let version_id = version_manifest.get_id();
+    let target_dir = self.versions_dir().join(version_id.to_string());
+    create_dir_all(&target_dir)?;
+    fs::write(target_dir.join(format!("{version_id}.json")), serde_json::to_vec_pretty(version_manifest)?)?;
+
+    self.resolved_versions_cache.remove(version_id);
+    if !self.local_cache.contains(version_id) {
+      self.local_cache.push(version_id.clone());
+    }
+    Ok(())
+  }
 }
 
 impl VersionManager {
   pub async fn resolve_inheritances(&mut self, version_manifest: VersionManifest) -> Result<VersionManifest, ResolveManifestError> {
-    resolve(version_manifest, self, &mut HashSet::new()).await
+    let version_id = version_manifest.id.clone();
+    let mut conflicts = vec![];
+    let resolved = resolve(version_manifest, self, &mut HashSet::new(), &mut conflicts).await?;
+    self.library_conflicts.insert(version_id, conflicts);
+    Ok(resolved)
   }
 
   pub async fn is_up_to_date(&mut self, version_manifest: &VersionManifest) -> bool {
@@ -241,10 +280,10 @@ impl VersionManager {
       }
 
       match self.resolve_inheritances(version_manifest.clone()).await {
-        Ok(resolved) => { self.has_all_files(&resolved, &OperatingSystem::get_current_platform()) }
+        Ok(resolved) => { self.has_all_files(&resolved) }
         Err(_) => {
-          error!("Failed to resolve version {}", version_manifest.get_id().to_string());
-          self.has_all_files(version_manifest, &OperatingSystem::get_current_platform())
+          error!("Failed to resolve version {}", version_manifest.get_id());
+          self.has_all_files(version_manifest)
         }
       }
     } else {
@@ -266,8 +305,8 @@ impl VersionManager {
 
 // Assets and Libraries
 impl VersionManager {
-  fn has_all_files(&self, local: &VersionManifest, os: &OperatingSystem) -> bool {
-    let required_files = local.get_required_files(os, &self.env_features);
+  fn has_all_files(&self, local: &VersionManifest) -> bool {
+    let required_files = local.get_required_files(&self.platform, &self.env_features);
     required_files.iter().all(|file| self.game_dir.join(file).is_file())
   }
 }
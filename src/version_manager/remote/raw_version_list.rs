@@ -20,4 +20,9 @@ impl RawVersionList {
   pub async fn fetch(client: &Client) -> Result<RawVersionList, LoadVersionError> {
     Ok(client.get(VERSION_MANIFEST_URL).send().await?.json::<RawVersionList>().await?)
   }
+
+  /// Returns the latest version of the given type, only releases and snapshots are tracked
+  pub fn get_latest(&self, release_type: &ReleaseType) -> Option<&MCVersion> {
+    self.latest.get(release_type)
+  }
 }
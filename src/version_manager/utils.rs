@@ -2,17 +2,18 @@ use std::collections::HashSet;
 
 use async_recursion::async_recursion;
 
-use crate::json::{ manifest::VersionManifest, MCVersion };
+use crate::json::{ manifest::{ argument::Argument, VersionManifest }, MCVersion };
 
-use super::{ error::ResolveManifestError, VersionManager };
+use super::{ error::ResolveManifestError, library_conflicts::{ merge_libraries, LibraryConflict, LibraryConflictStrategy }, VersionManager };
 
 #[async_recursion]
 pub async fn resolve(
-  mut version_manifest: VersionManifest,
+  version_manifest: VersionManifest,
   version_manager: &mut VersionManager,
-  inheritance_trace: &mut HashSet<MCVersion>
+  inheritance_trace: &mut HashSet<MCVersion>,
+  conflicts: &mut Vec<LibraryConflict>
 ) -> Result<VersionManifest, ResolveManifestError> {
-  if let Some(inherits_from) = version_manifest.inherits_from {
+  if let Some(inherits_from) = version_manifest.inherits_from.clone() {
     // Check for circular dependency errors
     if !inheritance_trace.insert(version_manifest.id.clone()) {
       let mut trace: Vec<String> = inheritance_trace.iter().map(ToString::to_string).collect();
@@ -28,59 +29,189 @@ pub async fn resolve(
     };
 
     // Recursively resolve the inherited version
-    let mut resolved_manifest = resolve(local_version, version_manager, inheritance_trace).await?;
-    resolved_manifest.inherits_from.take();
-    resolved_manifest.id = version_manifest.id;
-    resolved_manifest.updated_time = version_manifest.updated_time;
-    resolved_manifest.release_time = version_manifest.release_time;
-    resolved_manifest.release_type = version_manifest.release_type;
-
-    if let Some(minecraft_arguments) = version_manifest.minecraft_arguments {
-      resolved_manifest.minecraft_arguments.replace(minecraft_arguments);
-    }
+    let parent = resolve(local_version, version_manager, inheritance_trace, conflicts).await?;
+    let strategy = version_manager.library_conflict_strategy;
+    Ok(merge_inherited(version_manifest, parent, strategy, conflicts))
+  } else {
+    Ok(version_manifest)
+  }
+}
 
-    if let Some(main_class) = version_manifest.main_class {
-      resolved_manifest.main_class.replace(main_class);
-    }
+/// Merges an inheriting version onto its resolved parent. These are this crate's rules, based on the vanilla launcher's:
+/// - `id`, `time`, `releaseTime` and `type` are the child's, `inheritsFrom` is cleared
+/// - `minecraftArguments`, `mainClass`, `assets`, `assetIndex`, `jar`, `javaVersion` and `complianceLevel` are the child's if set
+/// - `minimumLauncherVersion` is the highest of both
+/// - `downloads` and `logging` are merged by type, the child's entries replacing the parent's
+/// - `libraries` are the child's first, conflicts resolved with `strategy`
+/// - `arguments` are the parent's then the child's, without the child's options the parent already has. The vanilla launcher
+///   appends all of the child's arguments, this avoids passing the same option twice when a loader repeats the parent's
+///   (see `argument_options` for what an option is)
+/// - `compatibilityRules` are the parent's then the child's, so the child's rules are applied last and take precedence
+pub(crate) fn merge_inherited(
+  child: VersionManifest,
+  mut parent: VersionManifest,
+  strategy: LibraryConflictStrategy,
+  conflicts: &mut Vec<LibraryConflict>
+) -> VersionManifest {
+  let parent_libraries = std::mem::take(&mut parent.libraries);
+  let libraries = merge_libraries(&child.id, child.libraries, parent_libraries, strategy, conflicts);
+
+  let mut arguments = parent.arguments;
+  for (arg_type, args) in child.arguments {
+    let merged = arguments.entry(arg_type).or_default();
+    let existing = argument_options(merged);
+    let new_options: Vec<Vec<Argument>> = argument_options(&args)
+      .into_iter()
+      .filter(|option| !existing.contains(option))
+      .map(<[Argument]>::to_vec)
+      .collect();
+    merged.extend(new_options.into_iter().flatten());
+  }
 
-    if let Some(assets) = version_manifest.assets {
-      resolved_manifest.assets.replace(assets);
-    }
+  let mut downloads = parent.downloads;
+  downloads.extend(child.downloads);
+  let mut logging = parent.logging;
+  logging.extend(child.logging);
+  let mut compatibility_rules = parent.compatibility_rules;
+  compatibility_rules.extend(child.compatibility_rules);
+
+  VersionManifest {
+    arguments,
+    minecraft_arguments: child.minecraft_arguments.or(parent.minecraft_arguments),
+    asset_index: child.asset_index.or(parent.asset_index),
+    assets: child.assets.or(parent.assets),
+    compatibility_rules,
+    compliance_level: child.compliance_level.or(parent.compliance_level),
+    downloads,
+    id: child.id,
+    inherits_from: None,
+    java_version: child.java_version.or(parent.java_version),
+    libraries,
+    logging,
+    main_class: child.main_class.or(parent.main_class),
+    jar: child.jar.or(parent.jar),
+    minimum_launcher_version: match (child.minimum_launcher_version, parent.minimum_launcher_version) {
+      (Some(child), Some(parent)) => Some(child.max(parent)),
+      (child, parent) => child.or(parent),
+    },
+    release_time: child.release_time,
+    updated_time: child.updated_time,
+    release_type: child.release_type,
+  }
+}
 
-    if let Some(jar) = version_manifest.jar {
-      resolved_manifest.jar.replace(jar);
+/// Splits arguments into options: a flag (or an argument with rules) and the plain values following it,
+/// e.g. `--add-opens`, `java.base/java.util.jar=cpw.mods.securejarhandler`.
+///
+/// Any value not starting with `-` is assumed to belong to the flag before it, so the same flag with another value
+/// is a different option.
+fn argument_options(args: &[Argument]) -> Vec<&[Argument]> {
+  let starts_option = |arg: &Argument| match arg {
+    Argument::Value(value) => value.value().first().is_none_or(|value| value.starts_with('-')),
+    Argument::Object { .. } => true,
+  };
+  let mut options = vec![];
+  let mut start = 0;
+  for (index, arg) in args.iter().enumerate().skip(1) {
+    if starts_option(arg) {
+      options.push(&args[start..index]);
+      start = index;
     }
+  }
+  if start < args.len() {
+    options.push(&args[start..]);
+  }
+  options
+}
 
-    if let Some(asset_index) = version_manifest.asset_index {
-      resolved_manifest.asset_index.replace(asset_index);
-    }
+#[cfg(test)]
+mod tests {
+  use std::{ fs, path::Path };
 
-    if !version_manifest.libraries.is_empty() {
-      let mut new_libraries = version_manifest.libraries;
-      new_libraries.append(&mut resolved_manifest.libraries);
-      resolved_manifest.libraries = new_libraries;
-    }
+  use crate::json::manifest::argument::ArgumentType;
 
-    if !version_manifest.arguments.is_empty() {
-      for (arg_type, mut args) in version_manifest.arguments {
-        if let Some(vec) = resolved_manifest.arguments.get_mut(&arg_type) {
-          vec.append(&mut args);
-        } else {
-          resolved_manifest.arguments.insert(arg_type, args);
-        }
-      }
-    }
+  use super::*;
 
-    if !version_manifest.compatibility_rules.is_empty() {
-      resolved_manifest.compatibility_rules.append(&mut version_manifest.compatibility_rules);
-    }
+  /// Trimmed manifests with placeholder hashes from `tests/fixtures/inheritance`, `<id>.resolved.json` being the merge
+  /// expected with this crate's rules. See the README there for where they come from.
+  fn fixture(name: &str) -> VersionManifest {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/inheritance").join(format!("{name}.json"));
+    serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+  }
 
-    if let Some(java_version) = version_manifest.java_version {
-      resolved_manifest.java_version.replace(java_version);
+  #[test]
+  fn test_merge_inherited_regression() {
+    let pairs = [("fabric-loader-0.15.11-1.20.1", "1.20.1"), ("1.20.1-forge-47.3.0", "1.20.1"), ("1.7.10-Forge10.13.4.1614-1.7.10", "1.7.10")];
+    for (child, parent) in pairs {
+      let mut conflicts = vec![];
+      let merged = merge_inherited(fixture(child), fixture(parent), LibraryConflictStrategy::PreferChild, &mut conflicts);
+      let expected = fixture(&format!("{child}.resolved"));
+      assert_eq!(serde_json::to_value(merged).unwrap(), serde_json::to_value(expected).unwrap(), "{child} onto {parent}");
+
+      if child.starts_with("1.7.10") {
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].replaced.to_string(), "com.google.guava:guava:15.0");
+      } else {
+        assert!(conflicts.is_empty());
+      }
     }
+  }
 
-    Ok(resolved_manifest)
-  } else {
-    Ok(version_manifest)
+  #[test]
+  fn test_merge_arguments() {
+    let manifest = |id: &str, jvm: serde_json::Value| -> VersionManifest {
+      let manifest = serde_json::json!({
+        "id": id,
+        "arguments": { "jvm": jvm },
+        "minimumLauncherVersion": 21,
+        "releaseTime": "2023-06-12T13:25:51+00:00",
+        "time": "2023-06-12T13:25:51+00:00",
+        "type": "release"
+      });
+      serde_json::from_value(manifest).unwrap()
+    };
+    let parent = manifest("parent", serde_json::json!(["-Djava.library.path=${natives_directory}", "-cp", "${classpath}", "--add-opens", "a"]));
+    let mut child = manifest("child", serde_json::json!(["-cp", "${classpath}", "--add-opens", "a", "--add-opens", "b", "-Dfoo"]));
+    child.minimum_launcher_version = Some(18.0);
+
+    let merged = merge_inherited(child, parent, LibraryConflictStrategy::default(), &mut vec![]);
+    let jvm: Vec<&String> = merged.arguments[&ArgumentType::Jvm]
+      .iter()
+      .flat_map(Argument::value)
+      .collect();
+    assert_eq!(jvm, ["-Djava.library.path=${natives_directory}", "-cp", "${classpath}", "--add-opens", "a", "--add-opens", "b", "-Dfoo"]);
+    assert_eq!(merged.minimum_launcher_version, Some(21.0));
+  }
+
+  #[test]
+  fn test_merge_repeated_flags() {
+    let manifest = |id: &str, jvm: serde_json::Value| -> VersionManifest {
+      let manifest = serde_json::json!({
+        "id": id,
+        "arguments": { "jvm": jvm },
+        "releaseTime": "2023-06-12T13:25:51+00:00",
+        "time": "2023-06-12T13:25:51+00:00",
+        "type": "release"
+      });
+      serde_json::from_value(manifest).unwrap()
+    };
+    let parent = manifest("parent", serde_json::json!(["--add-opens", "java.base/java.lang=ALL-UNNAMED"]));
+    let child = manifest("child", serde_json::json!([
+      "--add-opens", "java.base/java.util.jar=cpw.mods.securejarhandler",
+      "--add-opens", "java.base/java.lang.invoke=cpw.mods.securejarhandler",
+      "--add-opens", "java.base/java.lang=ALL-UNNAMED"
+    ]));
+
+    // The same flag with other values is kept, only the option the parent already has is dropped
+    let merged = merge_inherited(child, parent, LibraryConflictStrategy::default(), &mut vec![]);
+    let jvm: Vec<&String> = merged.arguments[&ArgumentType::Jvm]
+      .iter()
+      .flat_map(Argument::value)
+      .collect();
+    assert_eq!(jvm, [
+      "--add-opens", "java.base/java.lang=ALL-UNNAMED",
+      "--add-opens", "java.base/java.util.jar=cpw.mods.securejarhandler",
+      "--add-opens", "java.base/java.lang.invoke=cpw.mods.securejarhandler",
+    ]);
   }
 }